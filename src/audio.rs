@@ -0,0 +1,195 @@
+use macroquad::audio::{PlaySoundParams, Sound, load_sound_from_bytes, play_sound};
+use macroquad::prelude::*;
+
+use crate::constants::*;
+use crate::entities::GameEvent;
+use crate::settings::Settings;
+use crate::synth::{Envelope, Voice, Wave, encode_wav, mix, sequence};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    Wall,
+    Paddle,
+    BlockHit,
+    BlockBreak,
+    Explosion,
+    BallLost,
+    Win,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 7] = [
+        Sfx::Wall,
+        Sfx::Paddle,
+        Sfx::BlockHit,
+        Sfx::BlockBreak,
+        Sfx::Explosion,
+        Sfx::BallLost,
+        Sfx::Win,
+    ];
+
+    /// Block sounds come in one variant per tone of `BLOCK_TONE_SCALE`, the
+    /// rest in a few slightly detuned copies picked at random.
+    fn is_tonal(self) -> bool {
+        matches!(self, Sfx::BlockHit | Sfx::BlockBreak)
+    }
+
+    /// The effect's samples at `pitch` times its base frequencies.
+    pub fn synthesize(self, pitch: f32) -> Vec<f32> {
+        match self {
+            Sfx::Wall => Voice::new(Wave::Square, 1200.0 * pitch, 0.035, 0.2)
+                .sweep(1000.0 * pitch)
+                .render(),
+            Sfx::Paddle => mix(&[
+                Voice::new(Wave::Sine, 300.0 * pitch, 0.1, 0.6).sweep(520.0 * pitch),
+                Voice::new(Wave::Triangle, 600.0 * pitch, 0.06, 0.2).sweep(1040.0 * pitch),
+            ]),
+            Sfx::BlockHit => mix(&[
+                Voice::new(Wave::Triangle, 440.0 * pitch, 0.08, 0.6),
+                Voice::new(Wave::Sine, 880.0 * pitch, 0.04, 0.2),
+            ]),
+            Sfx::BlockBreak => mix(&[
+                Voice::new(Wave::Triangle, 660.0 * pitch, 0.15, 0.6).sweep(440.0 * pitch),
+                Voice::new(Wave::Noise, 4000.0, 0.1, 0.25),
+            ]),
+            Sfx::Explosion => mix(&[
+                Voice::new(Wave::Noise, 1500.0 * pitch, 0.5, 0.7)
+                    .sweep(200.0 * pitch)
+                    .envelope(Envelope {
+                        attack: 0.002,
+                        decay_power: 1.5,
+                    }),
+                Voice::new(Wave::Sine, 90.0 * pitch, 0.4, 0.6).sweep(40.0 * pitch),
+            ]),
+            Sfx::BallLost => Voice::new(Wave::Triangle, 420.0 * pitch, 0.55, 0.6)
+                .sweep(90.0 * pitch)
+                .envelope(Envelope::SUSTAIN)
+                .render(),
+            Sfx::Win => sequence(&[
+                Voice::new(Wave::Square, 523.3 * pitch, 0.09, 0.25),
+                Voice::new(Wave::Square, 659.3 * pitch, 0.09, 0.25),
+                Voice::new(Wave::Square, 784.0 * pitch, 0.09, 0.25),
+                Voice::new(Wave::Square, 1046.5 * pitch, 0.35, 0.25).envelope(Envelope::SUSTAIN),
+            ]),
+        }
+    }
+
+    /// The effect for an event, with the tone to play it at for block sounds.
+    fn for_event(event: &GameEvent) -> (Sfx, Option<usize>) {
+        match *event {
+            GameEvent::WallHit => (Sfx::Wall, None),
+            GameEvent::PaddleHit => (Sfx::Paddle, None),
+            GameEvent::BlockHit { color } => (Sfx::BlockHit, Some(block_tone(color))),
+            GameEvent::BlockDestroyed { color } => (Sfx::BlockBreak, Some(block_tone(color))),
+            GameEvent::Explosion => (Sfx::Explosion, None),
+            GameEvent::BallLost => (Sfx::BallLost, None),
+            GameEvent::LevelComplete | GameEvent::Win => (Sfx::Win, None),
+        }
+    }
+
+    fn pitches(self) -> Vec<f32> {
+        if self.is_tonal() {
+            BLOCK_TONE_SCALE
+                .iter()
+                .map(|semitones| 2f32.powf(*semitones as f32 / 12.0))
+                .collect()
+        } else {
+            (0..SFX_PITCH_VARIANTS)
+                .map(|i| {
+                    let t = i as f32 / (SFX_PITCH_VARIANTS - 1).max(1) as f32;
+                    1.0 + SFX_PITCH_VARIATION * (2.0 * t - 1.0)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Index into `BLOCK_TONE_SCALE` for a block colour. Warm colours (the top
+/// rows of the default layout) ring highest; grey and white steel lowest.
+pub fn block_tone(color: Color) -> usize {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let chroma = max - min;
+    if chroma < 0.1 {
+        return 0;
+    }
+
+    let hue = if max == color.r {
+        60.0 * ((color.g - color.b) / chroma).rem_euclid(6.0)
+    } else if max == color.g {
+        60.0 * ((color.b - color.r) / chroma + 2.0)
+    } else {
+        60.0 * ((color.r - color.g) / chroma + 4.0)
+    };
+    let steps = BLOCK_TONE_SCALE.len();
+    // Nearest bucket, not floor: red sits just under 360° and has to wrap to
+    // the top next to orange, and orange and yellow would otherwise share one
+    let bucket = (hue / 360.0 * steps as f32).round() as usize % steps;
+    steps - 1 - bucket
+}
+
+/// Synthesized sound effects. Anything that failed to load is simply silent.
+#[derive(Default)]
+pub struct Audio {
+    variants: Vec<Vec<Sound>>, // indexed in `Sfx::ALL` order
+}
+
+impl Audio {
+    /// Synthesizes every effect, yielding a frame between effects so startup
+    /// stays responsive. Meant to run as a coroutine.
+    pub async fn load() -> Audio {
+        let mut audio = Audio::default();
+        if !cfg!(feature = "audio") {
+            return audio;
+        }
+
+        for sfx in Sfx::ALL {
+            let mut variants = Vec::new();
+            for pitch in sfx.pitches() {
+                let wav = encode_wav(&sfx.synthesize(pitch));
+                match load_sound_from_bytes(&wav).await {
+                    Ok(sound) => variants.push(sound),
+                    Err(err) => eprintln!("Failed to load sound effect: {}", err),
+                }
+            }
+            audio.variants.push(variants);
+            next_frame().await;
+        }
+        audio
+    }
+
+    /// Plays the effect for each event, once per effect and tone even if it happened several times.
+    pub fn play_events(&self, events: &[GameEvent], settings: &Settings) {
+        let volume = settings.master_volume * settings.sfx_volume;
+        if volume <= 0.0 {
+            return;
+        }
+
+        let mut played = Vec::new();
+        for (sfx, tone) in events.iter().map(Sfx::for_event) {
+            if played.contains(&(sfx, tone)) {
+                continue;
+            }
+            played.push((sfx, tone));
+
+            let idx = Sfx::ALL
+                .iter()
+                .position(|other| *other == sfx)
+                .unwrap_or_default();
+            let Some(variants) = self.variants.get(idx).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            let variant = match tone {
+                Some(tone) => tone.min(variants.len() - 1),
+                None => rand::gen_range(0, variants.len()),
+            };
+            play_sound(
+                &variants[variant],
+                PlaySoundParams {
+                    looped: false,
+                    volume,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use macroquad::prelude::*;
+use std::path::PathBuf;
+
+use crate::constants::*;
+use crate::gamepad::{Gamepads, PadButton};
+use crate::storage::{data_dir, write_atomic};
+
+// --- Bindings File ---
+//
+// One action per line, `action = Key Key ...`, using the key names from
+// `KEYS` (e.g. `move_left = Left A`). Missing actions keep their defaults,
+// unknown actions and keys are reported and ignored.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Launch,
+    Pause,
+    Confirm,
+    MenuUp,
+    MenuDown,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Launch,
+        Action::Pause,
+        Action::Confirm,
+        Action::MenuUp,
+        Action::MenuDown,
+    ];
+
+    /// Key used for the action in the bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Launch => "launch",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::Launch => "LAUNCH",
+            Action::Pause => "PAUSE",
+            Action::Confirm => "CONFIRM",
+            Action::MenuUp => "MENU UP",
+            Action::MenuDown => "MENU DOWN",
+        }
+    }
+
+    /// Controller buttons for the action. These are fixed, only keys can be rebound.
+    fn pad_buttons(self) -> &'static [PadButton] {
+        match self {
+            Action::MoveLeft => &[PadButton::DPadLeft],
+            Action::MoveRight => &[PadButton::DPadRight],
+            Action::Launch | Action::Confirm => &[PadButton::South],
+            Action::Pause => &[PadButton::Start, PadButton::Select],
+            Action::MenuUp => &[PadButton::DPadUp],
+            Action::MenuDown => &[PadButton::DPadDown],
+        }
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::Left, KeyCode::A],
+            Action::MoveRight => vec![KeyCode::Right, KeyCode::D],
+            Action::Launch => vec![KeyCode::Space],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::P],
+            Action::Confirm => vec![KeyCode::Enter, KeyCode::KpEnter],
+            Action::MenuUp => vec![KeyCode::Up, KeyCode::W],
+            Action::MenuDown => vec![KeyCode::Down, KeyCode::S],
+        }
+    }
+}
+
+/// Every key that can be bound, named as in the bindings file.
+const KEYS: &[KeyCode] = &[
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::CapsLock,
+    KeyCode::ScrollLock,
+    KeyCode::NumLock,
+    KeyCode::PrintScreen,
+    KeyCode::Pause,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+    KeyCode::F25,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpDecimal,
+    KeyCode::KpDivide,
+    KeyCode::KpMultiply,
+    KeyCode::KpSubtract,
+    KeyCode::KpAdd,
+    KeyCode::KpEnter,
+    KeyCode::KpEqual,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightShift,
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    KEYS.iter()
+        .copied()
+        .find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
+
+/// Whether `key` may be bound to an action from the controls menu. Escape
+/// is kept back to cancel a rebind, though the bindings file may still use it.
+pub fn is_bindable(key: KeyCode) -> bool {
+    key != KeyCode::Escape && KEYS.contains(&key)
+}
+
+fn bindings_path() -> PathBuf {
+    data_dir().join(BINDINGS_FILE)
+}
+
+/// Keys bound to each action, indexed in `Action::ALL` order.
+pub struct Bindings {
+    keys: Vec<Vec<KeyCode>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keys: Action::ALL
+                .iter()
+                .map(|action| action.default_keys())
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn load() -> Self {
+        let path = bindings_path();
+        let mut bindings = Bindings::default();
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return bindings,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", path.display(), err);
+                return bindings;
+            }
+        };
+
+        for (line_idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, keys)) = line.split_once('=') else {
+                eprintln!(
+                    "{}:{}: expected action = keys",
+                    path.display(),
+                    line_idx + 1
+                );
+                continue;
+            };
+            let Some(action) = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name.trim())
+            else {
+                eprintln!(
+                    "{}:{}: unknown action '{}'",
+                    path.display(),
+                    line_idx + 1,
+                    name.trim()
+                );
+                continue;
+            };
+
+            let mut parsed = Vec::new();
+            for key in keys.split_whitespace() {
+                match parse_key(key) {
+                    Some(key) => parsed.push(key),
+                    None => eprintln!("{}:{}: unknown key '{}'", path.display(), line_idx + 1, key),
+                }
+            }
+            if parsed.is_empty() {
+                eprintln!(
+                    "{}:{}: no keys for {}, keeping the default",
+                    path.display(),
+                    line_idx + 1,
+                    action.name()
+                );
+            } else {
+                *bindings.keys_mut(action) = parsed;
+            }
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        let mut contents = String::new();
+        for action in Action::ALL {
+            let keys: Vec<String> = self.keys(action).iter().map(|key| key_name(*key)).collect();
+            contents += &format!("{} = {}\n", action.name(), keys.join(" "));
+        }
+        let path = bindings_path();
+        if let Err(err) = write_atomic(&path, &contents) {
+            eprintln!("Failed to save {}: {}", path.display(), err);
+        }
+    }
+
+    fn index(action: Action) -> usize {
+        Action::ALL
+            .iter()
+            .position(|other| *other == action)
+            .unwrap_or_default()
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        &self.keys[Self::index(action)]
+    }
+
+    fn keys_mut(&mut self, action: Action) -> &mut Vec<KeyCode> {
+        &mut self.keys[Self::index(action)]
+    }
+
+    /// Replaces the action's keys with `key`. Any other action holding `key`
+    /// takes over the action's old keys instead, so no key does two things.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        let old = std::mem::replace(self.keys_mut(action), vec![key]);
+        for other in Action::ALL {
+            let keys = self.keys_mut(other);
+            if other == action || !keys.contains(&key) {
+                continue;
+            }
+            keys.retain(|bound| *bound != key);
+            for old_key in &old {
+                if !keys.contains(old_key) {
+                    keys.push(*old_key);
+                }
+            }
+        }
+    }
+
+    /// Name of the action's first key, for prompts like "PRESS SPACE".
+    pub fn prompt(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or("?".to_owned(), |key| key_name(*key).to_uppercase())
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_down(*key))
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_pressed(*key))
+    }
+}
+
+/// Keyboard bindings plus any controller. Everything outside the input layer
+/// asks this about actions rather than looking at devices.
+pub struct Controls {
+    pub bindings: Bindings,
+    pub gamepads: Gamepads,
+}
+
+impl Controls {
+    pub fn load() -> Self {
+        Controls {
+            bindings: Bindings::load(),
+            gamepads: Gamepads::new(),
+        }
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings.pressed(action)
+            || action
+                .pad_buttons()
+                .iter()
+                .any(|button| self.gamepads.is_pressed(*button))
+    }
+
+    pub fn prompt(&self, action: Action) -> String {
+        self.bindings.prompt(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_swaps_a_key_held_by_another_action() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::Launch, KeyCode::P);
+
+        assert!(bindings.keys(Action::Launch) == [KeyCode::P]);
+        let pause = bindings.keys(Action::Pause);
+        assert!(!pause.contains(&KeyCode::P));
+        assert!(pause.contains(&KeyCode::Escape));
+        for key in Action::Launch.default_keys() {
+            assert!(pause.contains(&key));
+        }
+    }
+
+    #[test]
+    fn rebind_leaves_unrelated_actions_alone() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::Launch, KeyCode::F);
+
+        assert!(bindings.keys(Action::Launch) == [KeyCode::F]);
+        for action in Action::ALL.into_iter().filter(|a| *a != Action::Launch) {
+            assert!(bindings.keys(action) == action.default_keys().as_slice());
+        }
+    }
+
+    #[test]
+    fn escape_is_not_bindable() {
+        assert!(!is_bindable(KeyCode::Escape));
+        assert!(is_bindable(KeyCode::Space));
+    }
+}
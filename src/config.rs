@@ -0,0 +1,859 @@
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::constants::{ARENA_HEIGHT, ARENA_WIDTH};
+use crate::level::Campaign;
+
+// --- Config File ---
+//
+// Gameplay tuning, read once at startup from a TOML file:
+//
+//   [ball]
+//   speed = 400       # starting speed
+//   max_speed = 700
+//
+//   [effects]
+//   destroy_particles = [12, 50]
+//
+// Only the part of TOML the config needs is understood: `[section]` headers,
+// `key = value` lines whose value is a number or a `[min, max]` pair of whole
+// numbers, and `#` comments. Every key is optional and keeps its default when
+// left out. Unknown sections and keys, repeated keys and out-of-range values
+// are errors, so a typo never goes unnoticed.
+//
+// Replays and saves only play back faithfully under the config they were made
+// with, so both store `Config::fingerprint` and check it when loaded.
+
+#[derive(Clone)]
+pub struct PaddleConfig {
+    pub width: f32,
+    pub height: f32,
+    pub y: f32, // height of the paddle's bottom edge above the arena floor
+    pub speed: f32,
+    pub wide_factor: f32,
+}
+
+#[derive(Clone)]
+pub struct BallConfig {
+    pub radius: f32,
+    pub speed: f32,      // starting speed for levels that do not set their own
+    pub speed_ramp: f32, // speed gained per second of play
+    pub speed_per_hit: f32,
+    pub max_speed: f32,
+    pub death_line: f32, // how far below the floor a ball falls before it is lost
+    pub slow_factor: f32,
+    pub max_balls: usize,
+    pub multi_ball_spread: f32, // radians between split balls
+}
+
+#[derive(Clone)]
+pub struct BounceConfig {
+    pub min_angle: f32, // radians above horizontal a ball always leaves the paddle at
+    pub classic_max_angle: f32, // radians from vertical at the paddle's ends
+    pub velocity_transfer: f32, // share of the paddle's speed the ball picks up
+    pub min_speed_scale: f32, // slowest a physical bounce leaves a ball, as a share of the game speed
+    pub spin_factor: f32,     // spin (rad/s) per unit of paddle speed
+    pub max_spin: f32,
+    pub spin_decay: f32, // per second
+    pub stall_time: f32, // seconds without touching the paddle or a breakable block
+    pub stall_nudge_angle: f32,
+}
+
+#[derive(Clone)]
+pub struct BlockConfig {
+    pub height: f32,
+    pub padding: f32,
+    pub top_margin: f32, // gap between the top row and the arena ceiling
+    pub explosion_radius: f32,
+}
+
+#[derive(Clone)]
+pub struct ScoringConfig {
+    pub hit: i32,
+    pub destroy: i32, // bottom row, each row above adds row_bonus
+    pub row_bonus: i32,
+    pub combo_hits_per_step: u32, // block hits without touching the paddle per multiplier step
+    pub combo_max_multiplier: i32,
+}
+
+#[derive(Clone)]
+pub struct PowerUpConfig {
+    pub drop_chance: f32,
+    pub duration: f32,
+    pub capsule_speed: f32,
+    pub laser_speed: f32,
+    pub laser_cooldown: f32,
+}
+
+#[derive(Clone)]
+pub struct EffectsConfig {
+    // Particle counts are (min, max) with max exclusive
+    pub hit_particles: (i32, i32),
+    pub destroy_particles: (i32, i32),
+    pub deflect_particles: (i32, i32), // steel and other unbreakable blocks
+    pub explosion_particles: (i32, i32),
+    pub explosion_shake: f32, // seconds
+    pub ball_lost_shake: f32,
+}
+
+#[derive(Clone)]
+pub struct GameConfig {
+    pub starting_lives: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct Config {
+    pub paddle: PaddleConfig,
+    pub ball: BallConfig,
+    pub bounce: BounceConfig,
+    pub blocks: BlockConfig,
+    pub scoring: ScoringConfig,
+    pub powerups: PowerUpConfig,
+    pub effects: EffectsConfig,
+    pub game: GameConfig,
+}
+
+impl Default for PaddleConfig {
+    fn default() -> Self {
+        PaddleConfig {
+            width: 120.0,
+            height: 20.0,
+            y: 50.0,
+            speed: 500.0,
+            wide_factor: 1.5,
+        }
+    }
+}
+
+impl Default for BallConfig {
+    fn default() -> Self {
+        BallConfig {
+            radius: 10.0,
+            speed: 350.0,
+            speed_ramp: 1.5,
+            speed_per_hit: 1.0,
+            max_speed: 600.0,
+            death_line: 50.0,
+            slow_factor: 0.6,
+            max_balls: 12,
+            multi_ball_spread: 0.35,
+        }
+    }
+}
+
+impl Default for BounceConfig {
+    fn default() -> Self {
+        BounceConfig {
+            min_angle: 0.35,
+            classic_max_angle: 1.05,
+            velocity_transfer: 0.5,
+            min_speed_scale: 0.75,
+            spin_factor: 0.004,
+            max_spin: 3.0,
+            spin_decay: 1.5,
+            stall_time: 5.0,
+            stall_nudge_angle: 0.2,
+        }
+    }
+}
+
+impl Default for BlockConfig {
+    fn default() -> Self {
+        BlockConfig {
+            height: 25.0,
+            padding: 5.0,
+            top_margin: 50.0,
+            explosion_radius: 110.0,
+        }
+    }
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            hit: 5,
+            destroy: 10,
+            row_bonus: 5,
+            combo_hits_per_step: 4,
+            combo_max_multiplier: 5,
+        }
+    }
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        PowerUpConfig {
+            drop_chance: 0.15,
+            duration: 10.0,
+            capsule_speed: 150.0,
+            laser_speed: 700.0,
+            laser_cooldown: 0.3,
+        }
+    }
+}
+
+impl Default for EffectsConfig {
+    fn default() -> Self {
+        EffectsConfig {
+            hit_particles: (4, 10),
+            destroy_particles: (12, 50),
+            deflect_particles: (2, 5),
+            explosion_particles: (30, 60),
+            explosion_shake: 0.2,
+            ball_lost_shake: 0.3,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig { starting_lives: 3 }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Invalid(String), // values that are fine alone but not together
+    File {
+        path: PathBuf,
+        error: Box<ConfigError>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{}", err),
+            ConfigError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            ConfigError::Invalid(message) => write!(f, "{}", message),
+            ConfigError::File { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+// Scores are i32, so points per hit and the combo multiplier are kept small
+// enough that a block worth many row bonuses still scores without overflow
+const MAX_POINTS: i64 = 100_000;
+const MAX_MULTIPLIER: i64 = 100;
+const MAX_COUNT: i64 = i32::MAX as i64;
+
+const SECTIONS: [&str; 8] = [
+    "paddle", "ball", "bounce", "blocks", "scoring", "powerups", "effects", "game",
+];
+
+/// One `key = value` line, with the position of its value for error messages.
+struct Entry<'a> {
+    key: &'a str,
+    value: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Entry<'_> {
+    fn error(&self, message: String) -> ConfigError {
+        ConfigError::Parse {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+
+    fn number(&self) -> Result<f32, ConfigError> {
+        match self.value.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(self.error(format!(
+                "expected a number for {}, found '{}'",
+                self.key, self.value
+            ))),
+        }
+    }
+
+    fn within(&self, low: f32, high: f32, what: &str) -> Result<f32, ConfigError> {
+        let value = self.number()?;
+        if value < low || value > high {
+            return Err(self.error(format!("{} must be {}, found {}", self.key, what, value)));
+        }
+        Ok(value)
+    }
+
+    fn positive(&self) -> Result<f32, ConfigError> {
+        let value = self.number()?;
+        if value <= 0.0 {
+            return Err(self.error(format!("{} must be above zero, found {}", self.key, value)));
+        }
+        Ok(value)
+    }
+
+    fn non_negative(&self) -> Result<f32, ConfigError> {
+        self.within(0.0, f32::MAX, "zero or more")
+    }
+
+    fn fraction(&self) -> Result<f32, ConfigError> {
+        self.within(0.0, 1.0, "from 0 to 1")
+    }
+
+    /// An angle in radians short of a right angle.
+    fn angle(&self) -> Result<f32, ConfigError> {
+        let value = self.number()?;
+        if !(0.0..FRAC_PI_2).contains(&value) {
+            return Err(self.error(format!(
+                "{} must be at least 0 and below {:.3} radians, found {}",
+                self.key, FRAC_PI_2, value
+            )));
+        }
+        Ok(value)
+    }
+
+    fn whole(value: &str) -> Option<i64> {
+        value.trim().parse().ok()
+    }
+
+    fn integer(&self, min: i64, max: i64) -> Result<i64, ConfigError> {
+        match Entry::whole(self.value) {
+            Some(value) if value >= min && value <= max => Ok(value),
+            Some(value) => Err(self.error(format!(
+                "{} must be a whole number from {} to {}, found {}",
+                self.key, min, max, value
+            ))),
+            None => Err(self.error(format!(
+                "expected a whole number for {}, found '{}'",
+                self.key, self.value
+            ))),
+        }
+    }
+
+    /// A `[min, max]` pair of counts with max exclusive, so max must be above min.
+    fn range(&self) -> Result<(i32, i32), ConfigError> {
+        let bounds = self
+            .value
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|inner| inner.split_once(','))
+            .and_then(|(min, max)| Some((Entry::whole(min)?, Entry::whole(max)?)));
+        match bounds {
+            Some((min, max)) if 0 <= min && min < max && max <= i32::MAX as i64 => {
+                Ok((min as i32, max as i32))
+            }
+            Some((min, max)) => Err(self.error(format!(
+                "{} needs 0 <= min < max, found [{}, {}]",
+                self.key, min, max
+            ))),
+            None => Err(self.error(format!(
+                "expected [min, max] for {}, found '{}'",
+                self.key, self.value
+            ))),
+        }
+    }
+}
+
+fn apply(config: &mut Config, section: &str, entry: &Entry) -> Result<(), ConfigError> {
+    match (section, entry.key) {
+        ("paddle", "width") => config.paddle.width = entry.positive()?,
+        ("paddle", "height") => config.paddle.height = entry.positive()?,
+        ("paddle", "y") => config.paddle.y = entry.non_negative()?,
+        ("paddle", "speed") => config.paddle.speed = entry.positive()?,
+        ("paddle", "wide_factor") => config.paddle.wide_factor = entry.positive()?,
+
+        ("ball", "radius") => config.ball.radius = entry.positive()?,
+        ("ball", "speed") => config.ball.speed = entry.positive()?,
+        ("ball", "speed_ramp") => config.ball.speed_ramp = entry.non_negative()?,
+        ("ball", "speed_per_hit") => config.ball.speed_per_hit = entry.non_negative()?,
+        ("ball", "max_speed") => config.ball.max_speed = entry.positive()?,
+        ("ball", "death_line") => config.ball.death_line = entry.non_negative()?,
+        ("ball", "slow_factor") => config.ball.slow_factor = entry.positive()?,
+        ("ball", "max_balls") => config.ball.max_balls = entry.integer(1, MAX_COUNT)? as usize,
+        ("ball", "multi_ball_spread") => config.ball.multi_ball_spread = entry.angle()?,
+
+        ("bounce", "min_angle") => config.bounce.min_angle = entry.angle()?,
+        ("bounce", "classic_max_angle") => config.bounce.classic_max_angle = entry.angle()?,
+        ("bounce", "velocity_transfer") => {
+            config.bounce.velocity_transfer = entry.non_negative()?
+        }
+        ("bounce", "min_speed_scale") => {
+            config.bounce.min_speed_scale = entry.within(0.1, 1.0, "from 0.1 to 1")?
+        }
+        ("bounce", "spin_factor") => config.bounce.spin_factor = entry.non_negative()?,
+        ("bounce", "max_spin") => config.bounce.max_spin = entry.non_negative()?,
+        ("bounce", "spin_decay") => config.bounce.spin_decay = entry.non_negative()?,
+        ("bounce", "stall_time") => config.bounce.stall_time = entry.positive()?,
+        ("bounce", "stall_nudge_angle") => config.bounce.stall_nudge_angle = entry.angle()?,
+
+        ("blocks", "height") => config.blocks.height = entry.positive()?,
+        ("blocks", "padding") => config.blocks.padding = entry.non_negative()?,
+        ("blocks", "top_margin") => config.blocks.top_margin = entry.non_negative()?,
+        ("blocks", "explosion_radius") => config.blocks.explosion_radius = entry.non_negative()?,
+
+        ("scoring", "hit") => config.scoring.hit = entry.integer(0, MAX_POINTS)? as i32,
+        ("scoring", "destroy") => config.scoring.destroy = entry.integer(0, MAX_POINTS)? as i32,
+        ("scoring", "row_bonus") => config.scoring.row_bonus = entry.integer(0, MAX_POINTS)? as i32,
+        ("scoring", "combo_hits_per_step") => {
+            config.scoring.combo_hits_per_step = entry.integer(1, MAX_COUNT)? as u32
+        }
+        ("scoring", "combo_max_multiplier") => {
+            config.scoring.combo_max_multiplier = entry.integer(1, MAX_MULTIPLIER)? as i32
+        }
+
+        ("powerups", "drop_chance") => config.powerups.drop_chance = entry.fraction()?,
+        ("powerups", "duration") => config.powerups.duration = entry.positive()?,
+        ("powerups", "capsule_speed") => config.powerups.capsule_speed = entry.positive()?,
+        ("powerups", "laser_speed") => config.powerups.laser_speed = entry.positive()?,
+        ("powerups", "laser_cooldown") => config.powerups.laser_cooldown = entry.non_negative()?,
+
+        ("effects", "hit_particles") => config.effects.hit_particles = entry.range()?,
+        ("effects", "destroy_particles") => config.effects.destroy_particles = entry.range()?,
+        ("effects", "deflect_particles") => config.effects.deflect_particles = entry.range()?,
+        ("effects", "explosion_particles") => config.effects.explosion_particles = entry.range()?,
+        ("effects", "explosion_shake") => config.effects.explosion_shake = entry.non_negative()?,
+        ("effects", "ball_lost_shake") => config.effects.ball_lost_shake = entry.non_negative()?,
+
+        ("game", "starting_lives") => {
+            config.game.starting_lives = entry.integer(1, MAX_COUNT)? as u32
+        }
+
+        (section, key) => {
+            return Err(ConfigError::Parse {
+                line: entry.line,
+                column: 1,
+                message: format!("unknown key '{}' in [{}]", key, section),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Config {
+    /// FNV-1a hash of every value, stable across runs and platforms. Each
+    /// section is destructured in full, so a new field cannot be left out.
+    pub fn fingerprint(&self) -> u64 {
+        let Config {
+            paddle,
+            ball,
+            bounce,
+            blocks,
+            scoring,
+            powerups,
+            effects,
+            game,
+        } = self;
+        let PaddleConfig {
+            width,
+            height,
+            y,
+            speed: paddle_speed,
+            wide_factor,
+        } = paddle;
+        let BallConfig {
+            radius,
+            speed: ball_speed,
+            speed_ramp,
+            speed_per_hit,
+            max_speed,
+            death_line,
+            slow_factor,
+            max_balls,
+            multi_ball_spread,
+        } = ball;
+        let BounceConfig {
+            min_angle,
+            classic_max_angle,
+            velocity_transfer,
+            min_speed_scale,
+            spin_factor,
+            max_spin,
+            spin_decay,
+            stall_time,
+            stall_nudge_angle,
+        } = bounce;
+        let BlockConfig {
+            height: block_height,
+            padding,
+            top_margin,
+            explosion_radius,
+        } = blocks;
+        let ScoringConfig {
+            hit,
+            destroy,
+            row_bonus,
+            combo_hits_per_step,
+            combo_max_multiplier,
+        } = scoring;
+        let PowerUpConfig {
+            drop_chance,
+            duration,
+            capsule_speed,
+            laser_speed,
+            laser_cooldown,
+        } = powerups;
+        let EffectsConfig {
+            hit_particles,
+            destroy_particles,
+            deflect_particles,
+            explosion_particles,
+            explosion_shake,
+            ball_lost_shake,
+        } = effects;
+        let GameConfig { starting_lives } = game;
+
+        let floats = [
+            width,
+            height,
+            y,
+            paddle_speed,
+            wide_factor,
+            radius,
+            ball_speed,
+            speed_ramp,
+            speed_per_hit,
+            max_speed,
+            death_line,
+            slow_factor,
+            multi_ball_spread,
+            min_angle,
+            classic_max_angle,
+            velocity_transfer,
+            min_speed_scale,
+            spin_factor,
+            max_spin,
+            spin_decay,
+            stall_time,
+            stall_nudge_angle,
+            block_height,
+            padding,
+            top_margin,
+            explosion_radius,
+            drop_chance,
+            duration,
+            capsule_speed,
+            laser_speed,
+            laser_cooldown,
+            explosion_shake,
+            ball_lost_shake,
+        ];
+        let integers = [
+            *max_balls as i64,
+            *hit as i64,
+            *destroy as i64,
+            *row_bonus as i64,
+            *combo_hits_per_step as i64,
+            *combo_max_multiplier as i64,
+            hit_particles.0 as i64,
+            hit_particles.1 as i64,
+            destroy_particles.0 as i64,
+            destroy_particles.1 as i64,
+            deflect_particles.0 as i64,
+            deflect_particles.1 as i64,
+            explosion_particles.0 as i64,
+            explosion_particles.1 as i64,
+            *starting_lives as i64,
+        ];
+
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        let bytes = floats
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .chain(integers.iter().flat_map(|value| value.to_le_bytes()));
+        for byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+
+    /// Checks the rules that tie several values together, and the sizes
+    /// against the default arena.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+        if self.ball.max_speed < self.ball.speed {
+            return invalid(format!(
+                "ball.max_speed ({}) is below ball.speed ({})",
+                self.ball.max_speed, self.ball.speed
+            ));
+        }
+        let wide = self.paddle.width * self.paddle.wide_factor;
+        if wide > ARENA_WIDTH {
+            return invalid(format!(
+                "paddle.width ({}) times paddle.wide_factor ({}) is wider than the {} arena",
+                self.paddle.width, self.paddle.wide_factor, ARENA_WIDTH
+            ));
+        }
+        let max_radius = ARENA_WIDTH.min(ARENA_HEIGHT) / 8.0;
+        if self.ball.radius > max_radius {
+            return invalid(format!(
+                "ball.radius ({}) is above {}, an eighth of the arena",
+                self.ball.radius, max_radius
+            ));
+        }
+        let flattest = FRAC_PI_2 - self.bounce.min_angle;
+        if self.bounce.classic_max_angle > flattest {
+            return invalid(format!(
+                "bounce.classic_max_angle ({}) plus bounce.min_angle ({}) is above {:.3}, \
+                 so the paddle ends would aim flatter than the ball may leave",
+                self.bounce.classic_max_angle, self.bounce.min_angle, FRAC_PI_2
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the speeds levels set against the ball's speed limit.
+    pub fn check_campaign(&self, campaign: &Campaign) -> Result<(), ConfigError> {
+        for (index, level) in campaign.levels.iter().enumerate() {
+            if let Some(speed) = level.ball_speed
+                && speed > self.ball.max_speed
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "level {} sets speed = {}, above ball.max_speed ({})",
+                    index + 1,
+                    speed,
+                    self.ball.max_speed
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_config(source: &str) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    let mut section: Option<&str> = None;
+    let mut seen = HashSet::new();
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let content = line.split('#').next().unwrap_or_default().trim();
+        if content.is_empty() {
+            continue;
+        }
+        let error = |column: usize, message: String| ConfigError::Parse {
+            line: line_no,
+            column,
+            message,
+        };
+
+        if let Some(name) = content.strip_prefix('[') {
+            let Some(name) = name.strip_suffix(']').map(str::trim) else {
+                return Err(error(1, "section header is missing its ']'".to_owned()));
+            };
+            if !SECTIONS.contains(&name) {
+                return Err(error(
+                    1,
+                    format!(
+                        "unknown section [{}], expected one of {}",
+                        name,
+                        SECTIONS.join(", ")
+                    ),
+                ));
+            }
+            section = Some(name);
+            continue;
+        }
+
+        let Some(eq) = content.find('=') else {
+            return Err(error(1, "expected key = value".to_owned()));
+        };
+        let (key, raw_value) = (content[..eq].trim(), &content[eq + 1..]);
+        let value = raw_value.trim();
+        let Some(section) = section else {
+            return Err(error(
+                1,
+                format!("'{}' must go under a [section] header", key),
+            ));
+        };
+        if !seen.insert((section, key)) {
+            return Err(error(1, format!("{} is set twice in [{}]", key, section)));
+        }
+
+        // Byte offset of the value within the line: the indent stripped from
+        // `content`, the key and '=', then the spaces before the value.
+        let indent = line.len() - line.trim_start().len();
+        let offset = indent + eq + 1 + (raw_value.len() - raw_value.trim_start().len());
+        let column = line[..offset].chars().count() + 1;
+        let entry = Entry {
+            key,
+            value,
+            line: line_no,
+            column,
+        };
+        apply(&mut config, section, &entry)?;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+pub fn load_config(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+    let path = path.as_ref();
+    let wrap = |error| ConfigError::File {
+        path: path.to_owned(),
+        error: Box::new(error),
+    };
+    let source = std::fs::read_to_string(path).map_err(|err| wrap(err.into()))?;
+    parse_config(&source).map_err(wrap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{Level, parse_level};
+
+    /// Line, column and message of the parse error `source` must produce.
+    fn error_at(source: &str) -> (usize, usize, String) {
+        match parse_config(source) {
+            Err(ConfigError::Parse {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            Err(other) => panic!("expected a parse error, got {}", other),
+            Ok(_) => panic!("expected a parse error for {:?}", source),
+        }
+    }
+
+    fn invalid(source: &str) -> String {
+        match parse_config(source) {
+            Err(ConfigError::Invalid(message)) => message,
+            Err(other) => panic!("expected an invalid config, got {}", other),
+            Ok(_) => panic!("expected an invalid config for {:?}", source),
+        }
+    }
+
+    #[test]
+    fn reads_values_and_keeps_defaults() {
+        let config = parse_config(
+            "# tuning\n[ball]\nspeed = 400 # faster\n\n[effects]\nhit_particles = [2, 6]\n",
+        )
+        .unwrap();
+        assert_eq!(config.ball.speed, 400.0);
+        assert_eq!(config.effects.hit_particles, (2, 6));
+        assert_eq!(config.ball.radius, BallConfig::default().radius);
+        assert_eq!(
+            parse_config("").unwrap().fingerprint(),
+            Config::default().fingerprint()
+        );
+        assert_ne!(config.fingerprint(), Config::default().fingerprint());
+    }
+
+    #[test]
+    fn value_column_skips_padding() {
+        let (line, column, message) = error_at("[ball]\n  speed =  x\n");
+        assert_eq!((line, column), (2, 12));
+        assert_eq!(message, "expected a number for speed, found 'x'");
+        assert_eq!(error_at("[ball]\nspeed=fast\n").1, 7);
+    }
+
+    #[test]
+    fn rejects_repeats_and_unknowns() {
+        let (line, _, message) = error_at("[ball]\nspeed = 300\nspeed = 310\n");
+        assert_eq!(line, 3);
+        assert_eq!(message, "speed is set twice in [ball]");
+
+        assert_eq!(
+            error_at("[ball]\nsped = 3\n").2,
+            "unknown key 'sped' in [ball]"
+        );
+        assert!(error_at("[bal]\n").2.starts_with("unknown section [bal]"));
+        assert_eq!(
+            error_at("speed = 3\n").2,
+            "'speed' must go under a [section] header"
+        );
+        assert_eq!(error_at("[ball]\nspeed\n").2, "expected key = value");
+    }
+
+    #[test]
+    fn section_header_needs_closing_bracket() {
+        let (line, column, message) = error_at("[ball]\nspeed = 300\n[paddle\n");
+        assert_eq!((line, column), (3, 1));
+        assert_eq!(message, "section header is missing its ']'");
+    }
+
+    #[test]
+    fn particle_ranges() {
+        let range = |value: &str| parse_config(&format!("[effects]\nhit_particles = {}\n", value));
+        assert_eq!(range("[0, 1]").unwrap().effects.hit_particles, (0, 1));
+        assert_eq!(range("[ 3 ,7 ]").unwrap().effects.hit_particles, (3, 7));
+
+        let (_, column, message) = error_at("[effects]\nhit_particles = [5, 5]\n");
+        assert_eq!(column, 17);
+        assert_eq!(message, "hit_particles needs 0 <= min < max, found [5, 5]");
+        assert!(
+            error_at("[effects]\nhit_particles = [-1, 4]\n")
+                .2
+                .contains("0 <= min")
+        );
+        assert!(
+            error_at("[effects]\nhit_particles = 4\n")
+                .2
+                .contains("expected [min, max]")
+        );
+        assert!(
+            error_at("[effects]\nhit_particles = [1.5, 4]\n")
+                .2
+                .contains("expected")
+        );
+    }
+
+    #[test]
+    fn bounds_scoring_so_scores_cannot_overflow() {
+        let message = error_at("[scoring]\ndestroy = 2147483647\n").2;
+        assert_eq!(
+            message,
+            "destroy must be a whole number from 0 to 100000, found 2147483647"
+        );
+        assert!(
+            error_at("[scoring]\ncombo_max_multiplier = 101\n")
+                .2
+                .contains("1 to 100")
+        );
+        let config = parse_config("[scoring]\ndestroy = 100000\ncombo_max_multiplier = 100\n");
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn checks_values_against_each_other() {
+        assert!(invalid("[ball]\nspeed = 500\nmax_speed = 400\n").contains("max_speed"));
+        assert!(invalid("[paddle]\nwidth = 2000\n").contains("arena"));
+        assert!(invalid("[ball]\nradius = 500\n").contains("ball.radius"));
+        assert!(
+            invalid("[bounce]\nmin_angle = 0.8\nclassic_max_angle = 1.0\n").contains("flatter")
+        );
+    }
+
+    #[test]
+    fn level_speeds_stay_under_the_limit() {
+        let config = parse_config("[ball]\nspeed = 300\nmax_speed = 400\n").unwrap();
+        let campaign = |speed: f32| Campaign {
+            levels: vec![
+                Level::default(),
+                parse_level(&format!("speed = {}\nR\n", speed)).unwrap(),
+            ],
+            current: 0,
+        };
+        assert!(config.check_campaign(&campaign(400.0)).is_ok());
+        let Err(ConfigError::Invalid(message)) = config.check_campaign(&campaign(450.0)) else {
+            panic!("level above max_speed was accepted");
+        };
+        assert_eq!(
+            message,
+            "level 2 sets speed = 450, above ball.max_speed (400)"
+        );
+    }
+}
@@ -1,8 +1,34 @@
-pub const PADDLE_WIDTH: f32 = 120.0;
-pub const PADDLE_HEIGHT: f32 = 20.0;
-pub const BALL_RADIUS: f32 = 10.0;
-pub const BALL_SPEED: f32 = 350.0;
-pub const BLOCK_PADDING: f32 = 5.0;
-pub const BLOCK_COLS: usize = 10;
-pub const BLOCK_ROWS: usize = 5;
+pub const TICK_RATE: f32 = 240.0;
+pub const TICK_DT: f32 = 1.0 / TICK_RATE;
+pub const MAX_FRAME_TIME: f32 = 0.25; // cap catch-up after a stall
+pub const ARENA_WIDTH: f32 = 1000.0;
+pub const ARENA_HEIGHT: f32 = 800.0;
+pub const MAX_BALL_BOUNCES: usize = 8; // contacts resolved per ball per tick
+pub const BROADPHASE_CELL_SIZE: f32 = 64.0;
+pub const POPUP_LIFETIME: f32 = 0.8;
+pub const POPUP_RISE_SPEED: f32 = 60.0;
+pub const CAPSULE_WIDTH: f32 = 40.0;
+pub const CAPSULE_HEIGHT: f32 = 14.0;
 pub const TRAIL_SIZE: usize = 10;
+pub const CAMPAIGN_PATH: &str = "levels/campaign.txt";
+pub const CONFIG_PATH: &str = "config.toml"; // gameplay tuning, see config.rs
+pub const HIGH_SCORES_FILE: &str = "highscores.txt";
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
+pub const SAVE_FILE: &str = "savegame.txt";
+pub const SETTINGS_FILE: &str = "settings.txt";
+pub const BINDINGS_FILE: &str = "bindings.txt";
+pub const MENU_ITEM_SPACING: f32 = 60.0;
+pub const POINTER_SMOOTHING_RATE: f32 = 20.0; // per second, higher follows the pointer more tightly
+pub const POINTER_MAX_SPEED: f32 = 1500.0;
+#[cfg(feature = "gamepad")]
+pub const GAMEPAD_DEADZONE: f32 = 0.2;
+pub const GAMEPAD_MENU_THRESHOLD: f32 = 0.6;
+pub const SFX_PITCH_VARIANTS: usize = 5;
+pub const SFX_PITCH_VARIATION: f32 = 0.08; // up to 8% above or below the base pitch
+// Pentatonic semitones above the base block tone, one per 30 degrees of block hue
+pub const BLOCK_TONE_SCALE: [i32; 12] = [0, 2, 4, 7, 9, 12, 14, 16, 19, 21, 24, 26];
+pub const MUSIC_BARS: usize = 4; // loop length of every track
+pub const MUSIC_CROSSFADE_TIME: f32 = 1.5; // seconds for a layer to fade fully in or out
+pub const MUSIC_DUCK_TIME: f32 = 0.25;
+pub const MUSIC_PAUSE_DUCK: f32 = 0.3; // music volume while paused
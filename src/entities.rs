@@ -1,3 +1,6 @@
+use ::rand::distributions::uniform::SampleUniform;
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
 use macroquad::prelude::*;
 use std::collections::VecDeque;
 
@@ -5,28 +8,95 @@ use std::collections::VecDeque;
 
 pub struct Position(pub Vec2);
 pub struct Velocity(pub Vec2);
+pub struct PrevPosition(pub Vec2); // position at the start of the tick, for render interpolation
 
 pub struct ColorComp(pub Color);
-#[allow(dead_code)]
 pub struct CircleComp(pub f32);
 pub struct RectComp(pub Vec2);
 
+pub struct Health {
+    pub hits: u32,
+    pub max: u32,
+}
+
 pub struct TrailComp {
     pub positions: VecDeque<Vec2>,
     pub max_size: usize,
 }
 
+pub struct ScoreValue(pub i32); // points for breaking the block, before the combo multiplier
+pub struct PopupText(pub String);
+
+pub struct PowerUpComp(pub PowerUpKind);
+pub struct StuckToPaddle(pub f32); // x offset of the ball from the paddle's left edge
+pub struct Spin(pub f32); // radians per second the ball's heading turns, counter-clockwise
+pub struct SinceContact(pub f32); // seconds since the ball touched the paddle or a breakable block
+pub struct SpeedScale(pub f32); // ball speed over the game's ball speed, moved by physical bounces
+
+#[derive(Default)]
+pub struct ActiveEffects {
+    pub timers: Vec<(PowerUpKind, f32)>, // (effect, seconds remaining)
+    pub laser_cooldown: f32,
+}
+
+impl ActiveEffects {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers.iter().any(|(k, _)| *k == kind)
+    }
+}
+
 pub struct ParticleLifetime(pub f32);
 pub struct ParticleSize(pub f32);
 pub struct ParticleRotation(pub f32, pub f32); // (rotation, rotation_speed)
 pub struct ParticleDamping(pub f32);
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Normal,
+    Steel,
+    Explosive,
+    Portal(u8),
+}
+
+impl BlockKind {
+    /// Whether the block can be broken and must be cleared to finish the level.
+    pub fn is_destructible(self) -> bool {
+        matches!(self, BlockKind::Normal | BlockKind::Explosive)
+    }
+}
+
 // --- Marker Components (Tags) ---
 
 pub struct BallTag;
 pub struct PaddleTag;
 pub struct BlockTag;
 pub struct ParticleTag;
+pub struct CapsuleTag;
+pub struct LaserTag;
+pub struct PopupTag;
+
+// --- Resources ---
+
+/// Playfield bounds, in world units. Y is flipped: 0 is the bottom edge.
+#[derive(Clone, Copy)]
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Seeded source of all gameplay randomness, so a seed plus the inputs fully
+/// determine a game.
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
+
+    pub fn gen_range<T: SampleUniform + PartialOrd>(&mut self, low: T, high: T) -> T {
+        self.0.gen_range(low..high)
+    }
+}
 
 // --- Enums ---
 
@@ -35,6 +105,104 @@ pub enum GameState {
     Menu,
     Ready,
     Playing,
+    LevelComplete,
     GameOver,
     Win,
+    NameEntry,
+    HighScores,
+    Paused,
+    Settings,
+    Controls,
+}
+
+/// How the ball leaves the paddle. Classic aims by where the ball lands and
+/// keeps its speed; physical mirrors it and adds the paddle's motion and spin.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BounceModel {
+    Classic,
+    /// The paddle's motion changes the ball's speed as well as its heading.
+    /// The change is kept on the ball as a `SpeedScale`, so it rides on top
+    /// of the game's ramped speed, capped at `ball.max_speed`.
+    Physical,
+}
+
+impl BounceModel {
+    pub const ALL: [BounceModel; 2] = [BounceModel::Classic, BounceModel::Physical];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BounceModel::Classic => "classic",
+            BounceModel::Physical => "physical",
+        }
+    }
+
+    pub fn next(self) -> BounceModel {
+        let idx = BounceModel::ALL
+            .iter()
+            .position(|model| *model == self)
+            .unwrap_or_default();
+        BounceModel::ALL[(idx + 1) % BounceModel::ALL.len()]
+    }
+
+    pub fn parse(value: &str) -> Option<BounceModel> {
+        BounceModel::ALL
+            .into_iter()
+            .find(|model| model.name() == value)
+    }
+}
+
+/// Something that happened during a tick, for audio and effects to react to.
+#[derive(Clone, Copy)]
+pub enum GameEvent {
+    WallHit,
+    PaddleHit,
+    BlockHit { color: Color }, // the block survived, or cannot break
+    BlockDestroyed { color: Color },
+    Explosion,
+    BallLost,
+    LevelComplete,
+    Win,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    Wide,
+    SlowBall,
+    Sticky,
+    Laser,
+    ExtraLife,
+    MultiBall,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::Wide,
+        PowerUpKind::SlowBall,
+        PowerUpKind::Sticky,
+        PowerUpKind::Laser,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::MultiBall,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::Wide => "WIDE",
+            PowerUpKind::SlowBall => "SLOW",
+            PowerUpKind::Sticky => "STICKY",
+            PowerUpKind::Laser => "LASER",
+            PowerUpKind::ExtraLife => "1UP",
+            PowerUpKind::MultiBall => "MULTI",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Wide => SKYBLUE,
+            PowerUpKind::SlowBall => GREEN,
+            PowerUpKind::Sticky => PURPLE,
+            PowerUpKind::Laser => RED,
+            PowerUpKind::ExtraLife => GOLD,
+            PowerUpKind::MultiBall => WHITE,
+        }
+    }
 }
@@ -0,0 +1,536 @@
+use ::rand::RngCore;
+use hecs::{Entity, World};
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+use crate::config::Config;
+use crate::constants::*;
+use crate::entities::*;
+use crate::grid::BlockGrid;
+use crate::level::{Campaign, Level};
+use crate::replay::Recorder;
+use crate::systems::collision::{CollisionEvents, collision_system};
+use crate::systems::input::{FrameInput, input_system};
+use crate::systems::particle::particle_system;
+use crate::systems::physics::physics_system;
+use crate::systems::powerup::{PowerUpEvents, clear_effects, powerup_system, speed_factor};
+
+/// All gameplay state. Nothing in here touches the window, so a `Game` can be
+/// stepped headless with synthetic input.
+pub struct Game {
+    pub world: World,
+    pub state: GameState,
+    pub score: i32,
+    pub lives: u32,
+    pub campaign: Campaign,
+    pub arena: Arena,
+    pub rng: GameRng,
+    pub screenshake_time: f32,
+    pub recorder: Option<Recorder>,
+    pub resume_state: GameState, // where Paused returns to
+    pub events: Vec<GameEvent>,  // since the caller last drained them
+    pub blocks_left: usize,      // breakable blocks still standing
+    pub combo: u32,
+    pub grid: BlockGrid, // broadphase over the blocks, kept in step with spawns and despawns
+    pub bounce_model: BounceModel,
+    pub config: Config,  // tuning, fixed for the life of the game
+    pub ball_speed: f32, // ramps up during play, back to the level's base when a life is lost
+    accumulator: f32,
+    pending_launch: bool,
+}
+
+pub fn spawn_paddle(world: &mut World, config: &Config, x: f32, y: f32) {
+    world.spawn((
+        Position(vec2(x, y)),
+        PrevPosition(vec2(x, y)),
+        Velocity(Vec2::ZERO),
+        RectComp(vec2(config.paddle.width, config.paddle.height)),
+        ActiveEffects::default(),
+        PaddleTag,
+    ));
+}
+
+pub fn spawn_ball(world: &mut World, config: &Config, x: f32, y: f32) -> Entity {
+    world.spawn((
+        Position(vec2(x, y)),
+        PrevPosition(vec2(x, y)),
+        Velocity(Vec2::ZERO),
+        CircleComp(config.ball.radius),
+        Spin(0.0),
+        SinceContact(0.0),
+        SpeedScale(1.0),
+        TrailComp {
+            positions: VecDeque::with_capacity(TRAIL_SIZE),
+            max_size: TRAIL_SIZE,
+        },
+        BallTag,
+    ))
+}
+
+/// Splits every ball in play into three, fanning the copies out from its heading.
+fn split_balls(world: &mut World, config: &Config) {
+    let mut balls = Vec::new();
+    for (_id, (pos, vel, scale, _tag)) in
+        world.query_mut::<(&Position, &Velocity, &SpeedScale, &BallTag)>()
+    {
+        let heading = if vel.0 == Vec2::ZERO {
+            vec2(0.0, config.ball.speed)
+        } else {
+            vel.0
+        };
+        balls.push((pos.0, heading, scale.0));
+    }
+
+    let mut count = balls.len();
+    for (pos, heading, scale) in balls {
+        let spread = config.ball.multi_ball_spread;
+        for angle in [-spread, spread] {
+            if count >= config.ball.max_balls {
+                return;
+            }
+            let ball = spawn_ball(world, config, pos.x, pos.y);
+            if let Ok((vel, ball_scale)) =
+                world.query_one_mut::<(&mut Velocity, &mut SpeedScale)>(ball)
+            {
+                vel.0 = Vec2::from_angle(angle).rotate(heading);
+                ball_scale.0 = scale;
+            }
+            count += 1;
+        }
+    }
+}
+
+/// Holds every moving ball at the game's ball speed times its own speed scale,
+/// never above `max_speed`, and scaled down while the slow effect runs.
+fn hold_ball_speed(world: &mut World, config: &Config, speed: f32) {
+    let factor = speed_factor(world, config);
+    let max_scale = config.ball.max_speed / speed;
+    for (_id, (vel, scale, _tag)) in world
+        .query_mut::<(&mut Velocity, &mut SpeedScale, &BallTag)>()
+        .without::<&StuckToPaddle>()
+    {
+        scale.0 = scale.0.min(max_scale);
+        vel.0 = vel.0.normalize_or_zero() * (speed * factor * scale.0);
+    }
+}
+
+/// Spawns the level's blocks and returns how many of them can be broken.
+fn spawn_blocks(
+    world: &mut World,
+    grid: &mut BlockGrid,
+    config: &Config,
+    level: &Level,
+    arena: &Arena,
+) -> usize {
+    grid.reset(*arena);
+    let mut breakable = 0;
+    let top_row = level.rows.len().saturating_sub(1) as i32;
+    let cols = level.cols as f32;
+    let (height, padding) = (config.blocks.height, config.blocks.padding);
+    let width = (arena.width - (cols + 1.0) * padding) / cols;
+    for (row, cells) in level.rows.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            let Some(spec) = cell else {
+                continue;
+            };
+            if spec.kind.is_destructible() {
+                breakable += 1;
+            }
+            let pos = vec2(
+                padding + col as f32 * (width + padding),
+                arena.height - config.blocks.top_margin - row as f32 * (height + padding),
+            );
+            let size = vec2(width, height);
+            let id = world.spawn((
+                Position(pos),
+                RectComp(size),
+                ColorComp(spec.color),
+                Health {
+                    hits: spec.hits,
+                    max: spec.hits,
+                },
+                spec.kind,
+                ScoreValue(
+                    config.scoring.destroy.saturating_add(
+                        config
+                            .scoring
+                            .row_bonus
+                            .saturating_mul(top_row - row as i32),
+                    ),
+                ),
+                BlockTag,
+            ));
+            grid.insert(id, pos, size);
+        }
+    }
+    breakable
+}
+
+impl Game {
+    pub fn new(campaign: Campaign, arena: Arena, seed: u64, config: Config) -> Self {
+        let mut game = Game {
+            world: World::new(),
+            state: GameState::Menu,
+            score: 0,
+            lives: config.game.starting_lives,
+            campaign,
+            arena,
+            rng: GameRng::new(seed),
+            screenshake_time: 0.0,
+            recorder: None,
+            resume_state: GameState::Ready,
+            events: Vec::new(),
+            blocks_left: 0,
+            combo: 0,
+            grid: BlockGrid::new(arena, BROADPHASE_CELL_SIZE),
+            bounce_model: BounceModel::Classic,
+            config,
+            ball_speed: 0.0,
+            accumulator: 0.0,
+            pending_launch: false,
+        };
+        game.spawn_level(false);
+        game
+    }
+
+    /// The current level's starting ball speed.
+    fn base_ball_speed(&self) -> f32 {
+        self.campaign
+            .level()
+            .ball_speed
+            .unwrap_or(self.config.ball.speed)
+    }
+
+    /// Clears the world down to a centred paddle, a ball when `serve` is set,
+    /// and the current level's blocks. Spawn order decides query order, so it
+    /// is part of what a replay reproduces.
+    fn spawn_level(&mut self, serve: bool) {
+        self.world.clear();
+        spawn_paddle(
+            &mut self.world,
+            &self.config,
+            self.arena.width / 2.0 - self.config.paddle.width / 2.0,
+            self.config.paddle.y,
+        );
+        if serve {
+            self.serve_ball();
+        }
+        self.blocks_left = spawn_blocks(
+            &mut self.world,
+            &mut self.grid,
+            &self.config,
+            self.campaign.level(),
+            &self.arena,
+        );
+        self.ball_speed = self.base_ball_speed();
+    }
+
+    /// Puts a fresh ball in the middle, Ready snaps it onto the paddle.
+    fn serve_ball(&mut self) {
+        spawn_ball(
+            &mut self.world,
+            &self.config,
+            self.arena.width / 2.0,
+            self.config.paddle.y + self.config.ball.radius,
+        );
+    }
+
+    fn start_level(&mut self) {
+        self.spawn_level(true);
+        self.combo = 0;
+        self.state = GameState::Ready;
+    }
+
+    /// Leaves the menu and puts a ball on the paddle.
+    pub fn start(&mut self) {
+        self.serve_ball();
+        self.state = GameState::Ready;
+    }
+
+    pub fn next_level(&mut self) {
+        self.campaign.advance();
+        self.start_level();
+    }
+
+    /// Starts the current level over, keeping score and lives.
+    pub fn restart_level(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_restart_level();
+        }
+        self.screenshake_time = 0.0;
+        self.pending_launch = false;
+        self.start_level();
+    }
+
+    /// Freezes play. No ticks run until `resume`, so nothing is recorded either.
+    pub fn pause(&mut self) {
+        if let GameState::Ready | GameState::Playing = self.state {
+            self.resume_state = self.state;
+            self.state = GameState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let GameState::Paused | GameState::Settings | GameState::Controls = self.state {
+            self.state = self.resume_state;
+            self.pending_launch = false;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.score = 0;
+        self.lives = self.config.game.starting_lives;
+        self.campaign.restart();
+        self.start_level();
+    }
+
+    /// Resets to a fresh campaign behind the title screen. The rng is reseeded
+    /// and any recording restarts, so the next run replays on its own.
+    pub fn return_to_menu(&mut self) {
+        let seed = self.rng.0.next_u64();
+        self.rng = GameRng::new(seed);
+        if let Some(recorder) = &mut self.recorder {
+            *recorder = Recorder::new(seed, self.config.fingerprint(), self.arena);
+            if self.bounce_model != BounceModel::Classic {
+                recorder.record_bounce_model(self.bounce_model);
+            }
+        }
+
+        self.score = 0;
+        self.combo = 0;
+        self.lives = self.config.game.starting_lives;
+        self.campaign.restart();
+        self.screenshake_time = 0.0;
+        self.pending_launch = false;
+        self.spawn_level(false);
+        self.state = GameState::Menu;
+    }
+
+    pub fn set_arena(&mut self, arena: Arena) {
+        if arena.width == self.arena.width && arena.height == self.arena.height {
+            return;
+        }
+        self.arena = arena;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_arena(arena);
+        }
+    }
+
+    pub fn set_bounce_model(&mut self, model: BounceModel) {
+        if model == self.bounce_model {
+            return;
+        }
+        self.bounce_model = model;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_bounce_model(model);
+        }
+    }
+
+    /// Adds frame time to the clock and returns how many fixed ticks are due.
+    pub fn advance_clock(&mut self, frame_dt: f32) -> u32 {
+        self.accumulator += frame_dt.min(MAX_FRAME_TIME);
+        let mut ticks = 0;
+        while self.accumulator >= TICK_DT {
+            self.accumulator -= TICK_DT;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// Runs as many fixed ticks as the frame time allows. Launch presses are
+    /// held until the next tick so none are lost between ticks.
+    pub fn update(&mut self, input: &FrameInput, frame_dt: f32) {
+        self.pending_launch |= input.launch;
+
+        for _ in 0..self.advance_clock(frame_dt) {
+            let tick_input = FrameInput {
+                launch: self.pending_launch,
+                ..*input
+            };
+            self.pending_launch = false;
+            self.tick(&tick_input);
+        }
+    }
+
+    /// How much of the current level has been cleared, from 0.0 to 1.0.
+    pub fn level_progress(&self) -> f32 {
+        let total = self
+            .campaign
+            .level()
+            .rows
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|spec| spec.kind.is_destructible())
+            .count();
+        if total == 0 {
+            return 1.0;
+        }
+        1.0 - self.blocks_left.min(total) as f32 / total as f32
+    }
+
+    pub fn balls_in_play(&self) -> usize {
+        self.world.query::<&BallTag>().iter().count()
+    }
+
+    /// How far between the last two ticks the current frame is, for rendering.
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.accumulator / TICK_DT
+    }
+
+    /// Advances the simulation by one fixed tick. Launch also confirms the
+    /// level-complete and end screens; otherwise only Ready and Playing react to input.
+    pub fn tick(&mut self, input: &FrameInput) {
+        let dt = TICK_DT;
+        let mut input = input.quantized();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_input(&input);
+        }
+
+        for (_id, (prev, pos)) in self.world.query_mut::<(&mut PrevPosition, &Position)>() {
+            prev.0 = pos.0;
+        }
+
+        match self.state {
+            GameState::LevelComplete if input.launch => {
+                self.next_level();
+                input.launch = false;
+            }
+            GameState::GameOver | GameState::Win
+                if input.launch && self.screenshake_time <= 0.0 =>
+            {
+                self.restart();
+                input.launch = false;
+            }
+            _ => {}
+        }
+
+        if let GameState::Ready | GameState::Playing = self.state {
+            input_system(&mut self.world, &self.config, &self.arena, &input, dt);
+        }
+
+        if let GameState::Ready = self.state {
+            let mut paddle_pos = Vec2::ZERO;
+            let mut paddle_size = Vec2::ZERO;
+            for (_id, (pos, rect, _tag)) in
+                self.world.query_mut::<(&Position, &RectComp, &PaddleTag)>()
+            {
+                paddle_pos = pos.0;
+                paddle_size = rect.0;
+            }
+            for (_id, (pos, vel, _tag)) in self
+                .world
+                .query_mut::<(&mut Position, &mut Velocity, &BallTag)>()
+            {
+                pos.0 = vec2(
+                    paddle_pos.x + paddle_size.x / 2.0,
+                    paddle_pos.y + paddle_size.y + self.config.ball.radius,
+                );
+                vel.0 = Vec2::ZERO;
+            }
+
+            if input.launch {
+                for (_id, (vel, _tag)) in self.world.query_mut::<(&mut Velocity, &BallTag)>() {
+                    vel.0 = vec2(0.5, 1.0).normalize() * self.ball_speed;
+                }
+                self.state = GameState::Playing;
+            }
+        }
+
+        if let GameState::Playing = self.state {
+            physics_system(&mut self.world, dt);
+
+            let mut events = CollisionEvents {
+                screenshake_time: 0.0,
+                score_delta: 0,
+                ball_lost: false,
+                blocks_left: self.blocks_left,
+                events: Vec::new(),
+                combo: self.combo,
+            };
+            collision_system(
+                &mut self.world,
+                &mut self.grid,
+                &self.config,
+                &self.arena,
+                dt,
+                self.bounce_model,
+                &mut self.rng,
+                &mut events,
+            );
+            let block_hits = events
+                .events
+                .iter()
+                .filter(|event| {
+                    matches!(
+                        event,
+                        GameEvent::BlockHit { .. } | GameEvent::BlockDestroyed { .. }
+                    )
+                })
+                .count();
+            let ball = &self.config.ball;
+            self.ball_speed =
+                (self.ball_speed + ball.speed_ramp * dt + ball.speed_per_hit * block_hits as f32)
+                    .min(ball.max_speed);
+            self.events.append(&mut events.events);
+            self.blocks_left = events.blocks_left;
+            self.combo = events.combo;
+
+            self.score = self.score.saturating_add(events.score_delta);
+            if events.screenshake_time > 0.0 {
+                self.screenshake_time = events.screenshake_time;
+            }
+            // Clearing the level wins a tie with losing the last ball on the
+            // same tick, so the final block never costs a life.
+            let level_cleared = events.blocks_left == 0
+                && !self
+                    .world
+                    .query_mut::<(&BlockKind, &BlockTag)>()
+                    .into_iter()
+                    .any(|(_id, (kind, _tag))| kind.is_destructible());
+            if level_cleared {
+                let (state, event) = if self.campaign.is_final_level() {
+                    (GameState::Win, GameEvent::Win)
+                } else {
+                    (GameState::LevelComplete, GameEvent::LevelComplete)
+                };
+                self.state = state;
+                self.events.push(event);
+            } else if events.ball_lost {
+                clear_effects(&mut self.world, &self.config);
+                self.combo = 0;
+                self.ball_speed = self.base_ball_speed();
+                self.lives = self.lives.saturating_sub(1);
+                self.state = if self.lives == 0 {
+                    GameState::GameOver
+                } else {
+                    self.serve_ball();
+                    GameState::Ready
+                };
+            }
+            if let GameState::Playing = self.state {
+                let mut power_events = PowerUpEvents {
+                    extra_lives: 0,
+                    multi_ball: false,
+                };
+                powerup_system(
+                    &mut self.world,
+                    &self.config,
+                    dt,
+                    input.launch,
+                    &mut power_events,
+                );
+                self.lives += power_events.extra_lives;
+                if power_events.multi_ball {
+                    split_balls(&mut self.world, &self.config);
+                }
+                hold_ball_speed(&mut self.world, &self.config, self.ball_speed);
+            }
+            particle_system(&mut self.world, dt);
+        }
+
+        if self.screenshake_time > 0.0 {
+            self.screenshake_time -= dt;
+        }
+    }
+}
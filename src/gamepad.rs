@@ -0,0 +1,188 @@
+#[cfg(feature = "gamepad")]
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+
+use crate::constants::*;
+
+/// Controller buttons the game reacts to, independent of the backend.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PadButton {
+    South,
+    Start,
+    Select,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[cfg(feature = "gamepad")]
+fn pad_button(button: Button) -> Option<PadButton> {
+    Some(match button {
+        Button::South => PadButton::South,
+        Button::Start => PadButton::Start,
+        Button::Select => PadButton::Select,
+        Button::DPadUp => PadButton::DPadUp,
+        Button::DPadDown => PadButton::DPadDown,
+        Button::DPadLeft => PadButton::DPadLeft,
+        Button::DPadRight => PadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "gamepad")]
+const BUTTONS: [Button; 7] = [
+    Button::South,
+    Button::Start,
+    Button::Select,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+/// Scales a stick value so the deadzone edge reads as 0 and full tilt as 1.
+#[cfg(feature = "gamepad")]
+fn apply_deadzone(value: f32) -> f32 {
+    if value.abs() < GAMEPAD_DEADZONE {
+        0.0
+    } else {
+        value.signum() * (value.abs() - GAMEPAD_DEADZONE) / (1.0 - GAMEPAD_DEADZONE)
+    }
+}
+
+/// Snapshot of the active controller, refreshed once per frame by `poll`.
+/// Without the `gamepad` feature, or with no controller plugged in, it stays
+/// empty and everything falls back to the keyboard.
+#[derive(Default)]
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<Gilrs>,
+    #[cfg(feature = "gamepad")]
+    active: Option<GamepadId>,
+    stick: (f32, f32), // left stick after the deadzone, y up
+    stick_nav: i8,     // which way the stick was flicked for menus, to edge-trigger it
+    down: Vec<PadButton>,
+    pressed: Vec<PadButton>,
+}
+
+impl Gamepads {
+    #[cfg(feature = "gamepad")]
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|err| eprintln!("Gamepad support unavailable: {}", err))
+            .ok();
+        let active = gilrs
+            .as_ref()
+            .and_then(|gilrs| gilrs.gamepads().next().map(|(id, _)| id));
+        Gamepads {
+            gilrs,
+            active,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn new() -> Self {
+        Gamepads::default()
+    }
+
+    /// Drains controller events. Returns true when the controller in use was
+    /// unplugged, so the caller can pause.
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self) -> bool {
+        self.pressed.clear();
+        let Some(gilrs) = &mut self.gilrs else {
+            return false;
+        };
+
+        let mut lost = false;
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::Connected => {
+                    eprintln!("Gamepad connected: {}", gilrs.gamepad(event.id).name());
+                    self.active.get_or_insert(event.id);
+                }
+                EventType::Disconnected => {
+                    eprintln!("Gamepad disconnected: {}", gilrs.gamepad(event.id).name());
+                    if self.active == Some(event.id) {
+                        self.active = gilrs.gamepads().next().map(|(id, _)| id);
+                        lost = true;
+                    }
+                }
+                EventType::ButtonPressed(button, _) => {
+                    // Whichever controller was touched last takes over
+                    self.active = Some(event.id);
+                    if let Some(button) = pad_button(button) {
+                        self.pressed.push(button);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.down.clear();
+        self.stick = (0.0, 0.0);
+        if let Some(pad) = self.active.and_then(|id| gilrs.connected_gamepad(id)) {
+            for button in BUTTONS {
+                if pad.is_pressed(button)
+                    && let Some(button) = pad_button(button)
+                {
+                    self.down.push(button);
+                }
+            }
+            self.stick = (
+                apply_deadzone(pad.value(Axis::LeftStickX)),
+                apply_deadzone(pad.value(Axis::LeftStickY)),
+            );
+        }
+
+        self.update_stick_nav();
+        lost
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self) -> bool {
+        self.pressed.clear();
+        self.update_stick_nav();
+        false
+    }
+
+    /// Turns a stick flick into a single D-pad press so menus step one item at a time.
+    fn update_stick_nav(&mut self) {
+        let nav = if self.stick.1 > GAMEPAD_MENU_THRESHOLD {
+            1
+        } else if self.stick.1 < -GAMEPAD_MENU_THRESHOLD {
+            -1
+        } else {
+            0
+        };
+        if nav != self.stick_nav {
+            match nav {
+                1 => self.pressed.push(PadButton::DPadUp),
+                -1 => self.pressed.push(PadButton::DPadDown),
+                _ => {}
+            }
+        }
+        self.stick_nav = nav;
+    }
+
+    /// Horizontal steering from -1.0 to 1.0. The stick is proportional, the D-pad full speed.
+    pub fn move_x(&self) -> f32 {
+        let mut dpad = 0.0;
+        if self.is_down(PadButton::DPadLeft) {
+            dpad -= 1.0;
+        }
+        if self.is_down(PadButton::DPadRight) {
+            dpad += 1.0;
+        }
+        if dpad != 0.0 { dpad } else { self.stick.0 }
+    }
+
+    pub fn is_down(&self, button: PadButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    pub fn is_pressed(&self, button: PadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+}
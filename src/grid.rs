@@ -0,0 +1,87 @@
+use hecs::{Entity, World};
+use macroquad::prelude::*;
+use std::ops::RangeInclusive;
+
+use crate::entities::*;
+
+/// Uniform grid over the arena that buckets blocks by the cells their
+/// rectangles cover, so collision only looks at blocks near a ball. Blocks
+/// never move, so the grid only changes when blocks spawn or despawn.
+pub struct BlockGrid {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<Entity>>,
+}
+
+impl BlockGrid {
+    pub fn new(arena: Arena, cell_size: f32) -> Self {
+        let mut grid = BlockGrid {
+            cell_size,
+            cols: 0,
+            rows: 0,
+            cells: Vec::new(),
+        };
+        grid.reset(arena);
+        grid
+    }
+
+    /// Empties the grid and resizes it to cover the arena.
+    pub fn reset(&mut self, arena: Arena) {
+        self.cols = (arena.width / self.cell_size).ceil().max(1.0) as usize;
+        self.rows = (arena.height / self.cell_size).ceil().max(1.0) as usize;
+        self.cells = vec![Vec::new(); self.cols * self.rows];
+    }
+
+    /// Refills the grid from every block in the world, e.g. after loading a save.
+    pub fn rebuild(&mut self, world: &mut World, arena: Arena) {
+        self.reset(arena);
+        for (id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &BlockTag)>() {
+            self.insert(id, pos.0, rect.0);
+        }
+    }
+
+    // Anything past the edge of the arena lands in the border cells, so blocks
+    // left outside by a window resize are still found
+    fn cells_covering(
+        &self,
+        min: Vec2,
+        max: Vec2,
+    ) -> (RangeInclusive<usize>, RangeInclusive<usize>) {
+        let col = |x: f32| ((x / self.cell_size).floor().max(0.0) as usize).min(self.cols - 1);
+        let row = |y: f32| ((y / self.cell_size).floor().max(0.0) as usize).min(self.rows - 1);
+        (col(min.x)..=col(max.x), row(min.y)..=row(max.y))
+    }
+
+    pub fn insert(&mut self, id: Entity, pos: Vec2, size: Vec2) {
+        let (cols, rows) = self.cells_covering(pos, pos + size);
+        for row in rows {
+            for col in cols.clone() {
+                self.cells[row * self.cols + col].push(id);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: Entity, pos: Vec2, size: Vec2) {
+        let (cols, rows) = self.cells_covering(pos, pos + size);
+        for row in rows {
+            for col in cols.clone() {
+                self.cells[row * self.cols + col].retain(|other| *other != id);
+            }
+        }
+    }
+
+    /// Fills `out` with the blocks in the cells overlapping the rectangle from
+    /// `min` to `max`, each once and in a stable order.
+    pub fn query(&self, min: Vec2, max: Vec2, out: &mut Vec<Entity>) {
+        out.clear();
+        let (cols, rows) = self.cells_covering(min, max);
+        for row in rows {
+            for col in cols.clone() {
+                out.extend_from_slice(&self.cells[row * self.cols + col]);
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+}
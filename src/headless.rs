@@ -0,0 +1,225 @@
+use macroquad::prelude::*;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::constants::*;
+use crate::entities::*;
+use crate::game::{Game, spawn_ball};
+use crate::grid::BlockGrid;
+use crate::level::{BlockSpec, Campaign, Level};
+use crate::replay::Replay;
+use crate::systems::input::FrameInput;
+
+const HEADLESS_MAX_SECONDS: f32 = 1800.0;
+const BENCHMARK_TICKS: u32 = 4800;
+const BENCHMARK_BALLS: usize = 48;
+const BENCHMARK_COLS: usize = 40;
+const BENCHMARK_ROWS: usize = 22;
+const BENCHMARK_WALL: usize = 4; // thickness of the block frame around the empty middle
+
+/// Simple autopilot: keep the paddle under the lowest ball and launch whenever possible.
+fn autopilot(game: &mut Game, elapsed: f32) -> FrameInput {
+    let mut lowest: Option<Vec2> = None;
+    for (_id, (pos, _tag)) in game.world.query_mut::<(&Position, &BallTag)>() {
+        if lowest.is_none_or(|low| pos.0.y < low.y) {
+            lowest = Some(pos.0);
+        }
+    }
+
+    let mut move_dir = 0.0;
+    for (_id, (pos, rect, _tag)) in game.world.query_mut::<(&Position, &RectComp, &PaddleTag)>() {
+        if let Some(ball) = lowest {
+            // Sweep the aim point across the paddle so the ball does not settle into a loop
+            let aim = 0.5 + 0.2 * (elapsed * 0.37).sin();
+            let offset = ball.x - (pos.0.x + rect.0.x * aim);
+            move_dir = (offset / 20.0).clamp(-1.0, 1.0);
+        }
+    }
+
+    FrameInput {
+        move_dir,
+        launch: true,
+        target_x: None,
+    }
+}
+
+/// How a headless run stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Finish {
+    Victory,
+    GameOver,
+    TimeLimit,
+    ReplayFinished,
+}
+
+/// Where a headless run stood when it stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    pub finish: Finish,
+    pub elapsed: f32,
+    pub level: usize, // campaign index
+    pub levels: usize,
+    pub score: i32,
+    pub lives: u32,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let finish = match self.finish {
+            Finish::Victory => "victory",
+            Finish::GameOver => "game over",
+            Finish::TimeLimit => "time limit reached",
+            Finish::ReplayFinished => "replay finished",
+        };
+        write!(
+            f,
+            "{} after {:.1}s: level {}/{}, score {}, lives {}",
+            finish,
+            self.elapsed,
+            self.level + 1,
+            self.levels,
+            self.score,
+            self.lives
+        )
+    }
+}
+
+/// Plays the campaign without a window, driven by a replay if one is given and
+/// by the autopilot otherwise.
+pub fn run(game: &mut Game, mut replay: Option<Replay>) -> Outcome {
+    game.start();
+
+    let mut elapsed = 0.0;
+    let mut replay_ended = false;
+    while elapsed < HEADLESS_MAX_SECONDS {
+        let input = match &mut replay {
+            Some(replay) => match replay.next_step() {
+                Some(step) => {
+                    for event in step.events {
+                        event.apply(game);
+                    }
+                    step.input
+                }
+                None => {
+                    replay_ended = true;
+                    break;
+                }
+            },
+            None => autopilot(game, elapsed),
+        };
+        game.tick(&input);
+        game.events.clear();
+        elapsed += TICK_DT;
+
+        // The autopilot would confirm the end screen and start over, stop there instead
+        if replay.is_none() && matches!(game.state, GameState::GameOver | GameState::Win) {
+            break;
+        }
+    }
+
+    let finish = match game.state {
+        _ if replay_ended => Finish::ReplayFinished,
+        GameState::Win => Finish::Victory,
+        GameState::GameOver => Finish::GameOver,
+        _ => Finish::TimeLimit,
+    };
+    Outcome {
+        finish,
+        elapsed,
+        level: game.campaign.current,
+        levels: game.campaign.levels.len(),
+        score: game.score,
+        lives: game.lives,
+    }
+}
+
+/// A thick frame of blocks around an empty middle, so the benchmark balls stay
+/// boxed in with hundreds of blocks and never reach the paddle.
+fn benchmark_level() -> Level {
+    let rows = (0..BENCHMARK_ROWS)
+        .map(|row| {
+            (0..BENCHMARK_COLS)
+                .map(|col| {
+                    let inside = (BENCHMARK_WALL..BENCHMARK_ROWS - BENCHMARK_WALL).contains(&row)
+                        && (BENCHMARK_WALL..BENCHMARK_COLS - BENCHMARK_WALL).contains(&col);
+                    (!inside).then_some(BlockSpec {
+                        color: GRAY,
+                        hits: u32::MAX, // nothing breaks, so every run sees the same layout
+                        kind: BlockKind::Normal,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    Level {
+        rows,
+        cols: BENCHMARK_COLS,
+        ball_speed: None,
+    }
+}
+
+/// Runs the benchmark level with the given broadphase cell size and returns
+/// the time spent ticking and the final score.
+fn run_benchmark(config: &Config, cell_size: f32) -> (Duration, i32) {
+    let arena = Arena {
+        width: ARENA_WIDTH,
+        height: ARENA_HEIGHT,
+    };
+    let campaign = Campaign {
+        levels: vec![benchmark_level()],
+        current: 0,
+    };
+    let mut game = Game::new(campaign, arena, 0, config.clone());
+    game.grid = BlockGrid::new(arena, cell_size);
+    game.grid.rebuild(&mut game.world, arena);
+    game.state = GameState::Playing;
+
+    let mut rng = GameRng::new(0);
+    for _ in 0..BENCHMARK_BALLS {
+        let x = rng.gen_range(0.3, 0.7) * arena.width;
+        let y = rng.gen_range(0.45, 0.65) * arena.height;
+        let ball = spawn_ball(&mut game.world, config, x, y);
+        if let Ok(mut vel) = game.world.get::<&mut Velocity>(ball) {
+            vel.0 = Vec2::from_angle(rng.gen_range(0.0, std::f32::consts::TAU)) * config.ball.speed;
+        }
+    }
+
+    let input = FrameInput {
+        move_dir: 0.0,
+        launch: false,
+        target_x: None,
+    };
+    let start = Instant::now();
+    for _ in 0..BENCHMARK_TICKS {
+        game.tick(&input);
+        game.events.clear();
+    }
+    (start.elapsed(), game.score)
+}
+
+/// Times collision with the block grid against a single cell holding every
+/// block, which is the same as testing each ball against all of them.
+pub fn benchmark(config: &Config) {
+    let blocks = benchmark_level().rows.iter().flatten().flatten().count();
+    println!(
+        "{} blocks, {} balls, {} ticks",
+        blocks, BENCHMARK_BALLS, BENCHMARK_TICKS
+    );
+
+    let (brute_time, brute_score) = run_benchmark(config, ARENA_WIDTH.max(ARENA_HEIGHT));
+    println!("single cell: {:>9.1} ms", brute_time.as_secs_f64() * 1000.0);
+    let (grid_time, grid_score) = run_benchmark(config, BROADPHASE_CELL_SIZE);
+    println!("grid:        {:>9.1} ms", grid_time.as_secs_f64() * 1000.0);
+    println!(
+        "speedup {:.1}x",
+        brute_time.as_secs_f64() / grid_time.as_secs_f64()
+    );
+
+    if brute_score != grid_score {
+        eprintln!(
+            "runs disagree: score {} without the grid, {} with it",
+            brute_score, grid_score
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::constants::*;
+use crate::storage::{data_dir, write_atomic};
+
+// --- High Score File ---
+//
+// One entry per line, best first: `name<TAB>score<TAB>level<TAB>date`.
+// Lines that do not parse are skipped, so a damaged file loses only the
+// broken entries.
+
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    pub level: usize,
+    pub date: String,
+}
+
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    path: PathBuf,
+}
+
+fn parse_entry(line: &str) -> Option<HighScore> {
+    let mut fields = line.split('\t');
+    let entry = HighScore {
+        name: fields.next()?.to_owned(),
+        score: fields.next()?.parse().ok()?,
+        level: fields.next()?.parse().ok()?,
+        date: fields.next()?.to_owned(),
+    };
+    fields.next().is_none().then_some(entry)
+}
+
+/// Today's date as YYYY-MM-DD (UTC), from the days-since-epoch civil calendar conversion.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+impl HighScores {
+    /// Loads the table from the data directory. A missing or unreadable file
+    /// gives an empty table instead of an error.
+    pub fn load() -> Self {
+        let path = data_dir().join(HIGH_SCORES_FILE);
+        let mut entries = Vec::new();
+
+        match std::fs::read_to_string(&path) {
+            Ok(source) => {
+                for (line_idx, line) in source.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match parse_entry(line) {
+                        Some(entry) => entries.push(entry),
+                        None => eprintln!(
+                            "Skipping corrupt high score at {}:{}",
+                            path.display(),
+                            line_idx + 1
+                        ),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => eprintln!("Failed to read {}: {}", path.display(), err),
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        entries.truncate(MAX_HIGH_SCORES);
+        HighScores { entries, path }
+    }
+
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Inserts the entry in rank order, saves the table and returns the entry's rank.
+    pub fn insert(&mut self, entry: HighScore) -> usize {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+
+        if let Err(err) = self.save() {
+            eprintln!("Failed to save {}: {}", self.path.display(), err);
+        }
+        rank
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let mut contents = String::new();
+        for entry in &self.entries {
+            // Names come from the keyboard filter, but never let a tab or newline break the format
+            let name: String = entry.name.chars().filter(|c| !c.is_control()).collect();
+            contents += &format!(
+                "{}\t{}\t{}\t{}\n",
+                name, entry.score, entry.level, entry.date
+            );
+        }
+        write_atomic(&self.path, &contents)
+    }
+}
@@ -0,0 +1,449 @@
+use crate::entities::BlockKind;
+use macroquad::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// --- Level Format ---
+//
+// A level is a plain-text grid. Each line is one row of blocks (top row first)
+// and each whitespace-separated token is one cell:
+//
+//   .   empty cell
+//   R   red       O   orange    Y   yellow    G   green
+//   B   blue      P   purple    C   cyan      W   white
+//
+// A block letter may be followed by a digit 1-9 giving the number of hits it
+// takes to destroy, e.g. `R3`. Blocks without a digit break on the first hit.
+//
+// Special blocks:
+//
+//   S    steel, indestructible and not needed to clear the level
+//   X    explosive, destroys its neighbours (takes a hit count like colours)
+//   @N   portal on channel N (1-9); each channel must appear exactly twice
+//
+// Lines of the form `key = value` set options for the whole level:
+//
+//   speed = N   ball speed at the start of the level, in units per second
+//
+// Lines starting with '#' are comments, blank lines are ignored.
+//
+// A campaign manifest lists level files (relative to the manifest) in the
+// order they are played, one per line, with the same comment rules.
+
+pub struct BlockSpec {
+    pub color: Color,
+    pub hits: u32,
+    pub kind: BlockKind,
+}
+
+pub struct Level {
+    pub rows: Vec<Vec<Option<BlockSpec>>>,
+    pub cols: usize,
+    pub ball_speed: Option<f32>, // starting ball speed, the config's when not set
+}
+
+pub struct Campaign {
+    pub levels: Vec<Level>,
+    pub current: usize,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    File {
+        path: PathBuf,
+        error: Box<LevelError>,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "{}", err),
+            LevelError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            LevelError::File { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(err: std::io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+fn parse_color(c: char) -> Option<Color> {
+    Some(match c {
+        'R' => RED,
+        'O' => ORANGE,
+        'Y' => YELLOW,
+        'G' => GREEN,
+        'B' => BLUE,
+        'P' => PURPLE,
+        'C' => SKYBLUE,
+        'W' => WHITE,
+        _ => return None,
+    })
+}
+
+fn parse_cell(token: &str, line: usize, column: usize) -> Result<Option<BlockSpec>, LevelError> {
+    let error = |message: String| LevelError::Parse {
+        line,
+        column,
+        message,
+    };
+
+    let mut chars = token.chars();
+    let first = chars.next().unwrap_or('.');
+    if first == '.' {
+        if token.len() > 1 {
+            return Err(error(format!(
+                "empty cell '.' cannot have a suffix, found '{}'",
+                token
+            )));
+        }
+        return Ok(None);
+    }
+
+    let suffix = chars.as_str();
+    let digit = |what: &str| match suffix.parse::<u32>() {
+        Ok(n @ 1..=9) => Ok(n),
+        _ => Err(error(format!(
+            "expected {} from 1 to 9 after '{}', found '{}'",
+            what, first, suffix
+        ))),
+    };
+
+    let spec = match first {
+        'S' => {
+            if !suffix.is_empty() {
+                return Err(error(format!(
+                    "steel block takes no suffix, found '{}'",
+                    token
+                )));
+            }
+            BlockSpec {
+                color: GRAY,
+                hits: 1,
+                kind: BlockKind::Steel,
+            }
+        }
+        '@' => {
+            let channel = digit("a portal channel")?;
+            BlockSpec {
+                color: portal_color(channel),
+                hits: 1,
+                kind: BlockKind::Portal(channel as u8),
+            }
+        }
+        _ => {
+            let (color, kind) = match first {
+                'X' => (Color::from_rgba(255, 70, 20, 255), BlockKind::Explosive),
+                c => match parse_color(c) {
+                    Some(color) => (color, BlockKind::Normal),
+                    None => return Err(error(format!("unknown block type '{}'", first))),
+                },
+            };
+            let hits = if suffix.is_empty() {
+                1
+            } else {
+                digit("a hit count")?
+            };
+            BlockSpec { color, hits, kind }
+        }
+    };
+
+    Ok(Some(spec))
+}
+
+fn portal_color(channel: u32) -> Color {
+    const PALETTE: [Color; 3] = [VIOLET, MAGENTA, LIME];
+    PALETTE[(channel as usize - 1) % PALETTE.len()]
+}
+
+pub fn parse_level(source: &str) -> Result<Level, LevelError> {
+    let mut rows = Vec::new();
+    let mut cols = 0;
+    let mut ball_speed = None;
+    let mut portals: BTreeMap<u8, Vec<(usize, usize)>> = BTreeMap::new();
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(eq) = trimmed.find('=') {
+            let (key, raw_value) = (trimmed[..eq].trim(), &trimmed[eq + 1..]);
+            let value = raw_value.trim();
+            // Columns come from byte offsets into the untrimmed line.
+            let indent = line.len() - line.trim_start().len();
+            let key_column = line[..indent].chars().count() + 1;
+            let offset = indent + eq + 1 + (raw_value.len() - raw_value.trim_start().len());
+            let column = line[..offset].chars().count() + 1;
+            match key {
+                "speed" => match value.parse::<f32>() {
+                    Ok(speed) if speed > 0.0 => ball_speed = Some(speed),
+                    _ => {
+                        return Err(LevelError::Parse {
+                            line: line_no,
+                            column,
+                            message: format!("expected a positive ball speed, found '{}'", value),
+                        });
+                    }
+                },
+                _ => {
+                    return Err(LevelError::Parse {
+                        line: line_no,
+                        column: key_column,
+                        message: format!("unknown level option '{}'", key),
+                    });
+                }
+            }
+            continue;
+        }
+
+        let mut row = Vec::new();
+        let mut rest = line;
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let token_len = rest[start..]
+                .find(char::is_whitespace)
+                .unwrap_or(rest.len() - start);
+            let token = &rest[start..start + token_len];
+            let column = line[..line.len() - rest.len() + start].chars().count() + 1;
+
+            let cell = parse_cell(token, line_no, column)?;
+            if let Some(BlockSpec {
+                kind: BlockKind::Portal(channel),
+                ..
+            }) = cell
+            {
+                portals.entry(channel).or_default().push((line_no, column));
+            }
+            row.push(cell);
+            rest = &rest[start + token_len..];
+        }
+
+        if rows.is_empty() {
+            cols = row.len();
+        } else if row.len() != cols {
+            return Err(LevelError::Parse {
+                line: line_no,
+                column: 1,
+                message: format!("row has {} cells, expected {}", row.len(), cols),
+            });
+        }
+        rows.push(row);
+    }
+
+    for (channel, positions) in &portals {
+        if positions.len() != 2 {
+            let &(line, column) = positions.last().unwrap();
+            return Err(LevelError::Parse {
+                line,
+                column,
+                message: format!(
+                    "portal @{} must appear exactly twice, found {}",
+                    channel,
+                    positions.len()
+                ),
+            });
+        }
+    }
+
+    let breakable = rows
+        .iter()
+        .flatten()
+        .flatten()
+        .any(|spec| spec.kind.is_destructible());
+    if !breakable {
+        return Err(LevelError::Parse {
+            line: 1,
+            column: 1,
+            message: "level contains no breakable blocks".to_owned(),
+        });
+    }
+
+    Ok(Level {
+        rows,
+        cols,
+        ball_speed,
+    })
+}
+
+pub fn load_level(path: impl AsRef<Path>) -> Result<Level, LevelError> {
+    let source = std::fs::read_to_string(path)?;
+    parse_level(&source)
+}
+
+impl Default for Level {
+    /// The classic five-row wall, used when no level file can be loaded.
+    fn default() -> Self {
+        let row_colors = [RED, ORANGE, YELLOW, GREEN, BLUE];
+        Level {
+            rows: row_colors
+                .iter()
+                .map(|&color| {
+                    (0..10)
+                        .map(|_| {
+                            Some(BlockSpec {
+                                color,
+                                hits: 1,
+                                kind: BlockKind::Normal,
+                            })
+                        })
+                        .collect()
+                })
+                .collect(),
+            cols: 10,
+            ball_speed: None,
+        }
+    }
+}
+
+pub fn load_campaign(manifest: impl AsRef<Path>) -> Result<Campaign, LevelError> {
+    let manifest = manifest.as_ref();
+    let source = std::fs::read_to_string(manifest)?;
+    let dir = manifest.parent().unwrap_or(Path::new(""));
+
+    let mut levels = Vec::new();
+    for line in source.lines() {
+        let entry = line.trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+
+        let path = dir.join(entry);
+        let level = load_level(&path).map_err(|error| LevelError::File {
+            path,
+            error: Box::new(error),
+        })?;
+        levels.push(level);
+    }
+
+    if levels.is_empty() {
+        return Err(LevelError::Parse {
+            line: 1,
+            column: 1,
+            message: "campaign lists no levels".to_owned(),
+        });
+    }
+
+    Ok(Campaign { levels, current: 0 })
+}
+
+impl Campaign {
+    pub fn level(&self) -> &Level {
+        &self.levels[self.current]
+    }
+
+    pub fn is_final_level(&self) -> bool {
+        self.current + 1 >= self.levels.len()
+    }
+
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1).min(self.levels.len() - 1);
+    }
+
+    pub fn restart(&mut self) {
+        self.current = 0;
+    }
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Campaign {
+            levels: vec![Level::default()],
+            current: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line, column and message of the parse error `source` must produce.
+    fn error_at(source: &str) -> (usize, usize, String) {
+        match parse_level(source) {
+            Err(LevelError::Parse {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            Err(other) => panic!("expected a parse error, got {}", other),
+            Ok(_) => panic!("expected a parse error for {:?}", source),
+        }
+    }
+
+    #[test]
+    fn parses_cells_and_options() {
+        let level = parse_level("# comment\nspeed = 420\n\nR3 . S\n@1 X @1\n").unwrap();
+        assert_eq!(level.cols, 3);
+        assert_eq!(level.ball_speed, Some(420.0));
+        let top = &level.rows[0];
+        assert_eq!(top[0].as_ref().unwrap().hits, 3);
+        assert!(top[1].is_none());
+        assert!(top[2].as_ref().unwrap().kind == BlockKind::Steel);
+        assert!(level.rows[1][0].as_ref().unwrap().kind == BlockKind::Portal(1));
+        assert!(level.rows[1][1].as_ref().unwrap().kind == BlockKind::Explosive);
+    }
+
+    #[test]
+    fn option_value_column_skips_padding() {
+        let (line, column, message) = error_at("R R\n   speed =  fast\n");
+        assert_eq!((line, column), (2, 13));
+        assert!(message.contains("'fast'"), "{}", message);
+
+        let (line, column, message) = error_at("  pace = 3\nR\n");
+        assert_eq!((line, column), (1, 3));
+        assert_eq!(message, "unknown level option 'pace'");
+    }
+
+    #[test]
+    fn unknown_block_points_at_the_cell() {
+        let (line, column, message) = error_at("R Q\n");
+        assert_eq!((line, column), (1, 3));
+        assert_eq!(message, "unknown block type 'Q'");
+        assert_eq!(error_at("R  R0\n").1, 4);
+        assert_eq!(error_at(". .x\n").1, 3);
+    }
+
+    #[test]
+    fn rows_must_match_the_first() {
+        let (line, column, message) = error_at("R R R\n\nR R\n");
+        assert_eq!((line, column), (3, 1));
+        assert_eq!(message, "row has 2 cells, expected 3");
+    }
+
+    #[test]
+    fn portals_come_in_pairs() {
+        let (line, column, message) = error_at("R @2 .\n");
+        assert_eq!((line, column), (1, 3));
+        assert_eq!(message, "portal @2 must appear exactly twice, found 1");
+
+        let (line, column, message) = error_at("@1 R @1\n. @1 .\n");
+        assert_eq!((line, column), (2, 3));
+        assert_eq!(message, "portal @1 must appear exactly twice, found 3");
+
+        assert!(error_at("R @0 @0\n").2.contains("portal channel"));
+    }
+
+    #[test]
+    fn needs_a_breakable_block() {
+        assert_eq!(error_at("S . S\n").2, "level contains no breakable blocks");
+    }
+}
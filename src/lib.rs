@@ -0,0 +1,24 @@
+// --- BreakerBlock ---
+//
+// The game itself, split from the binary so integration tests can drive full
+// headless games. `main.rs` owns the window, menus and command line.
+
+pub mod audio;
+pub mod bindings;
+pub mod config;
+pub mod constants;
+pub mod entities;
+pub mod game;
+pub mod gamepad;
+pub mod grid;
+pub mod headless;
+pub mod highscores;
+pub mod level;
+pub mod music;
+pub mod replay;
+pub mod save;
+pub mod screens;
+pub mod settings;
+pub mod storage;
+pub mod synth;
+pub mod systems;
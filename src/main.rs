@@ -1,63 +1,24 @@
-mod constants;
-mod entities;
-mod systems;
-
-use hecs::World;
+use macroquad::experimental::coroutines::start_coroutine;
 use macroquad::prelude::*;
-use std::collections::VecDeque;
-
-use crate::constants::*;
-use crate::entities::*;
-use crate::systems::collision::{CollisionEvents, collision_system};
-use crate::systems::input::input_system;
-use crate::systems::particle::particle_system;
-use crate::systems::physics::physics_system;
-use crate::systems::render::render_system;
-
-fn spawn_paddle(world: &mut World, x: f32, y: f32) {
-    world.spawn((
-        Position(vec2(x, y)),
-        Velocity(Vec2::ZERO),
-        RectComp(vec2(PADDLE_WIDTH, PADDLE_HEIGHT)),
-        PaddleTag,
-    ));
-}
 
-fn spawn_ball(world: &mut World, x: f32, y: f32) {
-    world.spawn((
-        Position(vec2(x, y)),
-        Velocity(Vec2::ZERO),
-        CircleComp(BALL_RADIUS),
-        TrailComp {
-            positions: VecDeque::with_capacity(TRAIL_SIZE),
-            max_size: TRAIL_SIZE,
-        },
-        BallTag,
-    ));
-}
-
-fn spawn_blocks(world: &mut World, width: f32, height: f32) {
-    for row in 0..BLOCK_ROWS {
-        for col in 0..BLOCK_COLS {
-            let color = match row {
-                0 => RED,
-                1 => ORANGE,
-                2 => YELLOW,
-                3 => GREEN,
-                _ => BLUE,
-            };
-            world.spawn((
-                Position(vec2(
-                    BLOCK_PADDING + col as f32 * (width + BLOCK_PADDING),
-                    screen_height() - 50.0 - row as f32 * (height + BLOCK_PADDING),
-                )),
-                RectComp(vec2(width, height)),
-                ColorComp(color),
-                BlockTag,
-            ));
-        }
-    }
-}
+use breaker_block::audio::Audio;
+use breaker_block::bindings::{Action, Bindings, Controls, is_bindable, key_name};
+use breaker_block::config::{Config, load_config};
+use breaker_block::constants::*;
+use breaker_block::entities::*;
+use breaker_block::game::Game;
+use breaker_block::highscores::{HighScore, HighScores, today};
+use breaker_block::level::{Campaign, load_campaign};
+use breaker_block::music::Music;
+use breaker_block::replay::{Recorder, Replay};
+use breaker_block::save::{can_save, load_game, save_game};
+use breaker_block::screens::{
+    MenuList, dim_screen, draw_centered, draw_high_scores, draw_name_entry,
+};
+use breaker_block::settings::{Settings, next_volume};
+use breaker_block::systems::input::{PointerControl, read_input};
+use breaker_block::systems::render::{render_popups, render_system};
+use breaker_block::{headless, storage};
 
 fn _window_conf() -> Conf {
     Conf {
@@ -68,29 +29,352 @@ fn _window_conf() -> Conf {
     }
 }
 
-#[macroquad::main("BreakerBlock")]
-async fn main() {
-    let mut game_state = GameState::Menu;
-    let mut score = 0;
-    let mut screenshake_time = 0.0;
-    let mut world = World::new();
+/// The gameplay config from `--config`, else `config.toml` when there is one,
+/// else the defaults. A config that fails to load stops the game instead of
+/// silently playing with values nobody asked for.
+fn load_config_or_exit() -> Config {
+    let path = match arg_value("--config") {
+        Some(path) => path,
+        None if std::path::Path::new(CONFIG_PATH).exists() => CONFIG_PATH.to_owned(),
+        None => return Config::default(),
+    };
+    load_config(&path).unwrap_or_else(|err| {
+        eprintln!("Failed to load config {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// The campaign, or the built-in level when it fails to load. Levels that do
+/// not fit the config stop the game like a bad config does.
+fn load_campaign_or_default(config: &Config) -> Campaign {
+    let campaign = load_campaign(CAMPAIGN_PATH).unwrap_or_else(|err| {
+        eprintln!("Failed to load {}: {}", CAMPAIGN_PATH, err);
+        Campaign::default()
+    });
+    if let Err(err) = config.check_campaign(&campaign) {
+        eprintln!("{} does not fit the config: {}", CAMPAIGN_PATH, err);
+        std::process::exit(1);
+    }
+    campaign
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    Start,
+    Continue,
+    HighScores,
+    Quit,
+}
+
+impl MenuAction {
+    const ALL: [MenuAction; 4] = [
+        MenuAction::Start,
+        MenuAction::Continue,
+        MenuAction::HighScores,
+        MenuAction::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MenuAction::Start => "START",
+            MenuAction::Continue => "CONTINUE",
+            MenuAction::HighScores => "HIGH SCORES",
+            MenuAction::Quit => "QUIT",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PauseAction {
+    Resume,
+    RestartLevel,
+    Settings,
+    MainMenu,
+}
+
+impl PauseAction {
+    const ALL: [PauseAction; 4] = [
+        PauseAction::Resume,
+        PauseAction::RestartLevel,
+        PauseAction::Settings,
+        PauseAction::MainMenu,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseAction::Resume => "RESUME",
+            PauseAction::RestartLevel => "RESTART LEVEL",
+            PauseAction::Settings => "SETTINGS",
+            PauseAction::MainMenu => "MAIN MENU",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsItem {
+    ScreenShake,
+    Fullscreen,
+    ControlMode,
+    PointerSmoothing,
+    PointerSpeedCap,
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    BounceModel,
+    Controls,
+    Back,
+}
+
+impl SettingsItem {
+    const ALL: [SettingsItem; 11] = [
+        SettingsItem::ScreenShake,
+        SettingsItem::Fullscreen,
+        SettingsItem::ControlMode,
+        SettingsItem::PointerSmoothing,
+        SettingsItem::PointerSpeedCap,
+        SettingsItem::MasterVolume,
+        SettingsItem::SfxVolume,
+        SettingsItem::MusicVolume,
+        SettingsItem::BounceModel,
+        SettingsItem::Controls,
+        SettingsItem::Back,
+    ];
+
+    /// Volume sliders would do nothing in a build without sound.
+    fn is_shown(self) -> bool {
+        cfg!(feature = "audio")
+            || !matches!(
+                self,
+                SettingsItem::MasterVolume | SettingsItem::SfxVolume | SettingsItem::MusicVolume
+            )
+    }
+
+    fn label(self, settings: &Settings) -> String {
+        let on_off = |value: bool| if value { "ON" } else { "OFF" };
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+        match self {
+            SettingsItem::ScreenShake => format!("SCREEN SHAKE: {}", on_off(settings.screen_shake)),
+            SettingsItem::Fullscreen => format!("FULLSCREEN: {}", on_off(settings.fullscreen)),
+            SettingsItem::ControlMode => format!(
+                "PADDLE CONTROL: {}",
+                settings.control_mode.name().to_uppercase()
+            ),
+            SettingsItem::PointerSmoothing => {
+                format!("POINTER SMOOTHING: {}", on_off(settings.pointer_smoothing))
+            }
+            SettingsItem::PointerSpeedCap => {
+                format!("POINTER SPEED CAP: {}", on_off(settings.pointer_speed_cap))
+            }
+            SettingsItem::MasterVolume => {
+                format!("MASTER VOLUME: {}", percent(settings.master_volume))
+            }
+            SettingsItem::SfxVolume => format!("EFFECTS VOLUME: {}", percent(settings.sfx_volume)),
+            SettingsItem::MusicVolume => {
+                format!("MUSIC VOLUME: {}", percent(settings.music_volume))
+            }
+            SettingsItem::BounceModel => {
+                format!("BOUNCE: {}", settings.bounce_model.name().to_uppercase())
+            }
+            SettingsItem::Controls => "CONTROLS".to_owned(),
+            SettingsItem::Back => "BACK".to_owned(),
+        }
+    }
+
+    /// Toggles or steps the setting behind the item.
+    fn change(self, settings: &mut Settings) {
+        match self {
+            SettingsItem::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsItem::Fullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+                set_fullscreen(settings.fullscreen);
+            }
+            SettingsItem::ControlMode => settings.control_mode = settings.control_mode.next(),
+            SettingsItem::PointerSmoothing => {
+                settings.pointer_smoothing = !settings.pointer_smoothing
+            }
+            SettingsItem::PointerSpeedCap => {
+                settings.pointer_speed_cap = !settings.pointer_speed_cap
+            }
+            SettingsItem::MasterVolume => {
+                settings.master_volume = next_volume(settings.master_volume)
+            }
+            SettingsItem::SfxVolume => settings.sfx_volume = next_volume(settings.sfx_volume),
+            SettingsItem::MusicVolume => settings.music_volume = next_volume(settings.music_volume),
+            SettingsItem::BounceModel => settings.bounce_model = settings.bounce_model.next(),
+            SettingsItem::Controls | SettingsItem::Back => {}
+        }
+    }
+}
+
+/// Value following `flag` on the command line, e.g. `--seed 42`.
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
+}
 
-    let mut initial_dims = (screen_width(), screen_height());
-    if initial_dims.0 == 0.0 {
-        initial_dims = (1000.0, 800.0);
+/// Creates the game, taking seed and arena from the replay when playing one back.
+fn new_game(
+    replay: Option<&Replay>,
+    seed: u64,
+    arena: Arena,
+    record: bool,
+    config: Config,
+) -> Game {
+    let (seed, arena) = replay.map_or((seed, arena), |replay| (replay.seed, replay.arena));
+    let fingerprint = config.fingerprint();
+    let mut game = Game::new(load_campaign_or_default(&config), arena, seed, config);
+    if record {
+        game.recorder = Some(Recorder::new(seed, fingerprint, arena));
     }
+    game
+}
 
-    spawn_paddle(&mut world, initial_dims.0 / 2.0 - PADDLE_WIDTH / 2.0, 50.0);
-    // spawn_ball moved to Ready state transition
+fn save_recording(game: &Game, path: &Option<String>) {
+    if let (Some(recorder), Some(path)) = (&game.recorder, path)
+        && let Err(err) = recorder.save(path)
+    {
+        eprintln!("Failed to save replay {}: {}", path, err);
+    }
+}
 
-    let block_w = (initial_dims.0 - (BLOCK_COLS as f32 + 1.0) * BLOCK_PADDING) / BLOCK_COLS as f32;
-    spawn_blocks(&mut world, block_w, 25.0);
+fn main() {
+    let config = load_config_or_exit();
+    let record_path = arg_value("--record");
+    let replay = arg_value("--replay").map(|path| {
+        let replay = Replay::load(&path).unwrap_or_else(|err| {
+            eprintln!("Failed to load replay {}: {}", path, err);
+            std::process::exit(1);
+        });
+        // Any tuning difference desyncs playback, so refuse rather than show a different game
+        if replay.config_fingerprint != config.fingerprint() {
+            eprintln!(
+                "Replay {} was recorded with a different config (fingerprint {:016x}, current {:016x}); \
+                 pass the config it was made with using --config",
+                path,
+                replay.config_fingerprint,
+                config.fingerprint()
+            );
+            std::process::exit(1);
+        }
+        replay
+    });
+
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        headless::benchmark(&config);
+    } else if std::env::args().any(|arg| arg == "--headless") {
+        let arena = Arena {
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+        };
+        let seed = arg_value("--seed")
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or(0);
+        let mut game = new_game(replay.as_ref(), seed, arena, record_path.is_some(), config);
+        if replay.is_none()
+            && let Some(model) = arg_value("--bounce").and_then(|model| BounceModel::parse(&model))
+        {
+            game.set_bounce_model(model);
+        }
+        println!("{}", headless::run(&mut game, replay));
+        save_recording(&game, &record_path);
+    } else {
+        macroquad::Window::new("BreakerBlock", run(record_path, replay, config));
+    }
+}
+
+async fn run(record_path: Option<String>, mut replay: Option<Replay>, config: Config) {
+    let mut arena = Arena {
+        width: screen_width(),
+        height: screen_height(),
+    };
+    if arena.width == 0.0 {
+        arena = Arena {
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+        };
+    }
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let mut game = new_game(replay.as_ref(), seed, arena, record_path.is_some(), config);
+    if replay.is_some() {
+        game.start();
+    }
+
+    let mut replay_finished = false;
+    let mut high_scores = HighScores::load();
+    let mut player_name = String::new();
+    let mut new_rank = None;
+    let save_path = storage::data_dir().join(SAVE_FILE);
+    let mut has_save = replay.is_none() && save_path.exists();
+    let mut settings = Settings::load();
+    let mut controls = Controls::load();
+    let mut rebinding: Option<Action> = None;
+    let mut pointer = PointerControl::default();
+    let audio_loader = start_coroutine(Audio::load());
+    let mut audio = None;
+    let music_loader = start_coroutine(Music::load());
+    let mut music = None;
+    let mut main_menu = MenuList::default();
+    let mut pause_menu = MenuList::default();
+    let mut settings_menu = MenuList::default();
+    let mut controls_menu = MenuList::default();
+    set_fullscreen(settings.fullscreen);
+    prevent_quit();
 
     loop {
         let dt = get_frame_time();
+        if audio.is_none() {
+            audio = audio_loader.retrieve();
+        }
+        if music.is_none() {
+            music = music_loader.retrieve();
+        }
         clear_background(Color::from_rgba(15, 15, 25, 255));
 
-        match game_state {
+        if is_quit_requested() {
+            save_recording(&game, &record_path);
+            if replay.is_none()
+                && can_save(game.state)
+                && let Err(err) = save_game(&mut game, &save_path)
+            {
+                eprintln!("Failed to save game {}: {}", save_path.display(), err);
+            }
+            break;
+        }
+
+        // A score worth keeping goes to name entry instead of restarting
+        if replay.is_none()
+            && matches!(game.state, GameState::GameOver | GameState::Win)
+            && game.screenshake_time <= 0.0
+            && high_scores.qualifies(game.score)
+            && controls.pressed(Action::Launch)
+        {
+            player_name.clear();
+            clear_input_queue();
+            game.state = GameState::NameEntry;
+        }
+
+        // Losing the controller mid-game pauses rather than letting the ball drop
+        let pad_lost = controls.gamepads.poll();
+        if pad_lost && matches!(game.state, GameState::Ready | GameState::Playing) {
+            pause_menu.selected = 0;
+            game.pause();
+        } else if rebinding.is_none() && controls.pressed(Action::Pause) {
+            match game.state {
+                GameState::Ready | GameState::Playing => {
+                    pause_menu.selected = 0;
+                    game.pause();
+                }
+                GameState::Paused => game.resume(),
+                GameState::Settings => game.state = GameState::Paused,
+                GameState::Controls => game.state = GameState::Settings,
+                _ => {}
+            }
+        }
+
+        match game.state {
             GameState::Menu => {
                 let text = "BREAKER BLOCK";
                 let size = 80.0;
@@ -103,106 +387,151 @@ async fn main() {
                     SKYBLUE,
                 );
 
-                let subtext = "PRESS SPACE TO START";
-                let subsize = 30.0;
-                let subtext_size = measure_text(subtext, None, subsize as u16, 1.0);
-                draw_text(
-                    subtext,
-                    screen_width() / 2.0 - subtext_size.width / 2.0,
-                    screen_height() / 2.0 + 20.0,
-                    subsize,
-                    WHITE,
-                );
-
-                if is_key_pressed(KeyCode::Space) {
-                    let dims = (screen_width(), screen_height());
-                    spawn_ball(&mut world, dims.0 / 2.0, 50.0 + BALL_RADIUS);
-                    game_state = GameState::Ready;
+                let actions: Vec<MenuAction> = MenuAction::ALL
+                    .into_iter()
+                    .filter(|action| has_save || *action != MenuAction::Continue)
+                    .collect();
+                let items: Vec<String> = actions
+                    .iter()
+                    .map(|action| action.label().to_owned())
+                    .collect();
+                match main_menu
+                    .show(&items, screen_height() / 2.0 + 30.0, &controls)
+                    .map(|i| actions[i])
+                {
+                    Some(MenuAction::Start) => game.start(),
+                    Some(MenuAction::Continue) => {
+                        // A save resumes once; quitting mid-game writes a new one
+                        match load_game(
+                            &save_path,
+                            load_campaign_or_default(&game.config),
+                            game.config.clone(),
+                        ) {
+                            Ok(loaded) => {
+                                if record_path.is_some() {
+                                    eprintln!("Recording is not available for a resumed game");
+                                }
+                                game = loaded;
+                            }
+                            Err(err) => {
+                                eprintln!("Failed to load save {}: {}", save_path.display(), err)
+                            }
+                        }
+                        if let Err(err) = std::fs::remove_file(&save_path) {
+                            eprintln!("Failed to remove save {}: {}", save_path.display(), err);
+                        }
+                        has_save = false;
+                    }
+                    Some(MenuAction::HighScores) => {
+                        new_rank = None;
+                        game.state = GameState::HighScores;
+                    }
+                    Some(MenuAction::Quit) => {
+                        save_recording(&game, &record_path);
+                        break;
+                    }
+                    None => {}
                 }
             }
-            GameState::Ready | GameState::Playing | GameState::GameOver | GameState::Win => {
-                // Gameplay Logic
-                if let GameState::Ready | GameState::Playing = game_state {
-                    input_system(&mut world, dt);
+            GameState::NameEntry => {
+                // The queue pops the most recent character first
+                let mut typed = Vec::new();
+                while let Some(c) = get_char_pressed() {
+                    typed.push(c);
                 }
-
-                if let GameState::Ready = game_state {
-                    let mut paddle_x = 0.0;
-                    let mut paddle_y = 0.0;
-                    for (_id, (pos, _tag)) in world.query_mut::<(&Position, &PaddleTag)>() {
-                        paddle_x = pos.0.x;
-                        paddle_y = pos.0.y;
-                    }
-                    for (_id, (pos, vel, _tag)) in
-                        world.query_mut::<(&mut Position, &mut Velocity, &BallTag)>()
+                for c in typed.into_iter().rev() {
+                    if (c.is_ascii_alphanumeric() || c == ' ')
+                        && player_name.chars().count() < MAX_NAME_LENGTH
                     {
-                        pos.0 = vec2(
-                            paddle_x + PADDLE_WIDTH / 2.0,
-                            paddle_y + PADDLE_HEIGHT + BALL_RADIUS,
-                        );
-                        vel.0 = Vec2::ZERO;
-                    }
-
-                    if is_key_pressed(KeyCode::Space) {
-                        for (_id, (vel, _tag)) in world.query_mut::<(&mut Velocity, &BallTag)>() {
-                            vel.0 = vec2(0.5, 1.0).normalize() * BALL_SPEED;
-                        }
-                        game_state = GameState::Playing;
+                        player_name.push(c.to_ascii_uppercase());
                     }
                 }
+                if is_key_pressed(KeyCode::Backspace) {
+                    player_name.pop();
+                }
 
-                if let GameState::Playing = game_state {
-                    const SUBSTEPS: usize = 10;
-                    let sub_dt = dt / SUBSTEPS as f32;
-
-                    for _ in 0..SUBSTEPS {
-                        physics_system(&mut world, sub_dt);
+                if controls.pressed(Action::Confirm) {
+                    let name = player_name.trim();
+                    new_rank = Some(high_scores.insert(HighScore {
+                        name: if name.is_empty() { "PLAYER" } else { name }.to_owned(),
+                        score: game.score,
+                        level: game.campaign.current + 1,
+                        date: today(),
+                    }));
+                    game.state = GameState::HighScores;
+                }
 
-                        let mut events = CollisionEvents {
-                            screenshake_time: 0.0,
-                            score_delta: 0,
-                            game_over: false,
-                            blocks_left: 0,
-                        };
-                        collision_system(&mut world, &mut events);
+                draw_name_entry(game.score, &player_name, &controls);
+            }
+            GameState::HighScores => {
+                draw_high_scores(&high_scores, new_rank, &controls);
 
-                        score += events.score_delta;
-                        if events.screenshake_time > 0.0 {
-                            screenshake_time = events.screenshake_time;
-                        }
-                        if events.game_over {
-                            game_state = GameState::GameOver;
-                            break;
-                        }
-                        if events.blocks_left == 0 {
-                            let mut blocks_exist = false;
-                            for (_id, _tag) in world.query_mut::<&BlockTag>() {
-                                blocks_exist = true;
-                                break;
-                            }
-                            if !blocks_exist {
-                                game_state = GameState::Win;
+                if controls.pressed(Action::Confirm)
+                    || controls.pressed(Action::Launch)
+                    || controls.pressed(Action::Pause)
+                {
+                    main_menu.selected = 0;
+                    game.return_to_menu();
+                }
+            }
+            GameState::Ready
+            | GameState::Playing
+            | GameState::LevelComplete
+            | GameState::GameOver
+            | GameState::Win
+            | GameState::Paused
+            | GameState::Settings
+            | GameState::Controls => {
+                // Gameplay Logic
+                let paused = matches!(
+                    game.state,
+                    GameState::Paused | GameState::Settings | GameState::Controls
+                );
+                let was_over = matches!(game.state, GameState::GameOver | GameState::Win);
+                match &mut replay {
+                    _ if paused => {}
+                    Some(replay) => {
+                        for _ in 0..game.advance_clock(dt) {
+                            let Some(step) = replay.next_step() else {
+                                replay_finished = true;
                                 break;
+                            };
+                            for event in step.events {
+                                event.apply(&mut game);
                             }
+                            game.tick(&step.input);
                         }
                     }
-                    particle_system(&mut world, dt);
+                    None => {
+                        game.set_arena(Arena {
+                            width: screen_width(),
+                            height: screen_height(),
+                        });
+                        game.set_bounce_model(settings.bounce_model);
+                        let input = read_input(&controls, &settings, &mut pointer, dt);
+                        game.update(&input, dt);
+                    }
+                }
+                if let Some(audio) = &audio {
+                    audio.play_events(&game.events, &settings);
+                }
+                game.events.clear();
+                if !was_over && matches!(game.state, GameState::GameOver | GameState::Win) {
+                    save_recording(&game, &record_path);
                 }
 
                 // Camera and Rendering (Persistent across gameplay and end-screens)
-                if screenshake_time > 0.0 {
-                    screenshake_time -= dt;
-                }
-                let camera_offset = if screenshake_time > 0.0 {
+                let camera_offset = if settings.screen_shake && game.screenshake_time > 0.0 {
                     vec2(rand::gen_range(-5.0, 5.0), rand::gen_range(-5.0, 5.0))
                 } else {
                     Vec2::ZERO
                 };
 
                 // Clear background logic: Only render if playing/ready OR shaking
-                if game_state == GameState::Ready
-                    || game_state == GameState::Playing
-                    || screenshake_time > 0.0
+                if game.state == GameState::Ready
+                    || game.state == GameState::Playing
+                    || paused
+                    || game.screenshake_time > 0.0
                 {
                     push_camera_state();
                     let mut camera = Camera2D {
@@ -218,18 +547,95 @@ async fn main() {
                     camera.zoom.x *= -1.0;
                     set_camera(&camera);
 
-                    render_system(&mut world);
+                    let alpha = game.interpolation_alpha();
+                    render_system(&mut game.world, alpha);
 
                     pop_camera_state();
+                    render_popups(&mut game.world, &camera, alpha);
                 }
 
-                draw_text(&format!("SCORE: {}", score), 20.0, 40.0, 40.0, WHITE);
+                let score_text = format!("SCORE: {}", game.score);
+                let score_text_size = measure_text(&score_text, None, 40, 1.0);
+                draw_text(&score_text, 20.0, 40.0, 40.0, WHITE);
+                draw_text(
+                    &format!("LIVES: {}", game.lives),
+                    score_text_size.width + 60.0,
+                    40.0,
+                    40.0,
+                    WHITE,
+                );
+
+                // Active power-up timers
+                for (_id, (effects, _tag)) in game.world.query_mut::<(&ActiveEffects, &PaddleTag)>()
+                {
+                    for (i, (kind, remaining)) in effects.timers.iter().enumerate() {
+                        draw_text(
+                            &format!("{} {:.0}", kind.label(), remaining.ceil()),
+                            20.0 + i as f32 * 130.0,
+                            75.0,
+                            28.0,
+                            kind.color(),
+                        );
+                    }
+                }
+
+                let level_text = format!(
+                    "LEVEL {}/{}",
+                    game.campaign.current + 1,
+                    game.campaign.levels.len()
+                );
+                let level_text_size = measure_text(&level_text, None, 40, 1.0);
+                draw_text(
+                    &level_text,
+                    screen_width() - level_text_size.width - 20.0,
+                    40.0,
+                    40.0,
+                    WHITE,
+                );
+
+                if replay.is_some() {
+                    let text = if replay_finished {
+                        "REPLAY FINISHED"
+                    } else {
+                        "REPLAY"
+                    };
+                    let text_size = measure_text(text, None, 30, 1.0);
+                    draw_text(
+                        text,
+                        screen_width() - text_size.width - 20.0,
+                        75.0,
+                        30.0,
+                        ORANGE,
+                    );
+                }
 
                 // End-Screen Overlays
-                if (game_state == GameState::GameOver || game_state == GameState::Win)
-                    && screenshake_time <= 0.0
+                if let GameState::LevelComplete = game.state {
+                    let text = "LEVEL COMPLETE";
+                    let text_size = measure_text(text, None, 80, 1.0);
+                    draw_text(
+                        text,
+                        screen_width() / 2.0 - text_size.width / 2.0,
+                        screen_height() / 2.0,
+                        80.0,
+                        SKYBLUE,
+                    );
+
+                    let subtext = format!("PRESS {} TO CONTINUE", controls.prompt(Action::Launch));
+                    let subtext_size = measure_text(&subtext, None, 30, 1.0);
+                    draw_text(
+                        &subtext,
+                        screen_width() / 2.0 - subtext_size.width / 2.0,
+                        screen_height() / 2.0 + 50.0,
+                        30.0,
+                        WHITE,
+                    );
+                }
+
+                if (game.state == GameState::GameOver || game.state == GameState::Win)
+                    && game.screenshake_time <= 0.0
                 {
-                    let (text, color) = if let GameState::Win = game_state {
+                    let (text, color) = if let GameState::Win = game.state {
                         ("VICTORY!", GREEN)
                     } else {
                         ("GAME OVER", RED)
@@ -243,31 +649,134 @@ async fn main() {
                         color,
                     );
 
-                    let subtext = "PRESS SPACE TO RESTART";
-                    let subtext_size = measure_text(subtext, None, 30, 1.0);
+                    let subtext = if replay.is_none() && high_scores.qualifies(game.score) {
+                        format!(
+                            "PRESS {} TO ENTER YOUR NAME",
+                            controls.prompt(Action::Launch)
+                        )
+                    } else {
+                        format!("PRESS {} TO RESTART", controls.prompt(Action::Launch))
+                    };
+                    let subtext_size = measure_text(&subtext, None, 30, 1.0);
                     draw_text(
-                        subtext,
+                        &subtext,
                         screen_width() / 2.0 - subtext_size.width / 2.0,
                         screen_height() / 2.0 + 50.0,
                         30.0,
                         WHITE,
                     );
+                }
 
-                    if is_key_pressed(KeyCode::Space) {
-                        score = 0;
-                        world.clear();
-                        let dims = (screen_width(), screen_height());
-                        spawn_paddle(&mut world, dims.0 / 2.0 - PADDLE_WIDTH / 2.0, 50.0);
-                        spawn_ball(&mut world, dims.0 / 2.0, 50.0 + BALL_RADIUS);
-                        let block_w = (dims.0 - (BLOCK_COLS as f32 + 1.0) * BLOCK_PADDING)
-                            / BLOCK_COLS as f32;
-                        spawn_blocks(&mut world, block_w, 25.0);
-                        game_state = GameState::Ready;
+                if let GameState::Paused = game.state {
+                    dim_screen();
+                    draw_centered("PAUSED", screen_height() / 2.0 - 120.0, 80.0, SKYBLUE);
+
+                    // Restarting mid-replay would desync the recorded inputs
+                    let actions: Vec<PauseAction> = PauseAction::ALL
+                        .into_iter()
+                        .filter(|action| replay.is_none() || *action != PauseAction::RestartLevel)
+                        .collect();
+                    let items: Vec<String> = actions
+                        .iter()
+                        .map(|action| action.label().to_owned())
+                        .collect();
+                    match pause_menu
+                        .show(&items, screen_height() / 2.0 - 30.0, &controls)
+                        .map(|i| actions[i])
+                    {
+                        Some(PauseAction::Resume) => game.resume(),
+                        Some(PauseAction::RestartLevel) => game.restart_level(),
+                        Some(PauseAction::Settings) => {
+                            settings_menu.selected = 0;
+                            game.state = GameState::Settings;
+                        }
+                        Some(PauseAction::MainMenu) => {
+                            save_recording(&game, &record_path);
+                            game.return_to_menu();
+                        }
+                        None => {}
+                    }
+                } else if let GameState::Settings = game.state {
+                    dim_screen();
+                    draw_centered("SETTINGS", screen_height() / 2.0 - 320.0, 80.0, SKYBLUE);
+
+                    let shown: Vec<SettingsItem> = SettingsItem::ALL
+                        .into_iter()
+                        .filter(|item| item.is_shown())
+                        .collect();
+                    let items: Vec<String> =
+                        shown.iter().map(|item| item.label(&settings)).collect();
+                    match settings_menu
+                        .show(&items, screen_height() / 2.0 - 260.0, &controls)
+                        .map(|i| shown[i])
+                    {
+                        Some(SettingsItem::Controls) => {
+                            controls_menu.selected = 0;
+                            game.state = GameState::Controls;
+                        }
+                        Some(SettingsItem::Back) => game.state = GameState::Paused,
+                        Some(item) => {
+                            item.change(&mut settings);
+                            settings.save();
+                        }
+                        None => {}
+                    }
+                } else if let GameState::Controls = game.state {
+                    dim_screen();
+                    let top = screen_height() / 2.0 - 220.0;
+                    draw_centered("CONTROLS", top, 80.0, SKYBLUE);
+
+                    if let Some(action) = rebinding {
+                        draw_centered(
+                            &format!("PRESS A KEY FOR {}", action.label()),
+                            screen_height() / 2.0,
+                            40.0,
+                            GOLD,
+                        );
+                        draw_centered("ESC TO CANCEL", screen_height() / 2.0 + 50.0, 30.0, GRAY);
+                        match get_last_key_pressed() {
+                            Some(KeyCode::Escape) => rebinding = None,
+                            Some(key) if is_bindable(key) => {
+                                controls.bindings.rebind(action, key);
+                                controls.bindings.save();
+                                rebinding = None;
+                            }
+                            _ => {}
+                        }
+                    } else {
+                        let mut items: Vec<String> = Action::ALL
+                            .iter()
+                            .map(|action| {
+                                let keys: Vec<String> = controls
+                                    .bindings
+                                    .keys(*action)
+                                    .iter()
+                                    .map(|key| key_name(*key).to_uppercase())
+                                    .collect();
+                                format!("{}: {}", action.label(), keys.join(", "))
+                            })
+                            .collect();
+                        items.push("RESET DEFAULTS".to_owned());
+                        items.push("BACK".to_owned());
+
+                        match controls_menu.show(&items, top + 80.0, &controls) {
+                            Some(i) if i < Action::ALL.len() => rebinding = Some(Action::ALL[i]),
+                            Some(i) if i == Action::ALL.len() => {
+                                controls.bindings = Bindings::default();
+                                controls.bindings.save();
+                            }
+                            Some(_) => game.state = GameState::Settings,
+                            None => {}
+                        }
                     }
                 }
             }
         }
 
+        if let Some(music) = &mut music {
+            music.update(&game, &settings, dt);
+        }
+
         next_frame().await
     }
 }
@@ -0,0 +1,212 @@
+use macroquad::audio::{
+    PlaySoundParams, Sound, load_sound_from_bytes, play_sound, set_sound_volume, stop_sound,
+};
+use macroquad::prelude::*;
+
+use crate::constants::*;
+use crate::entities::GameState;
+use crate::game::Game;
+use crate::settings::Settings;
+use crate::synth::{Envelope, SAMPLE_RATE, Voice, Wave, encode_wav, overlay};
+
+// --- Music ---
+//
+// Every level loops a short synthesized track built from layers of equal
+// length: bass and pad, then drums, then an arpeggio. All layers of a track
+// start together and keep playing, so bringing one in or out is only a
+// volume fade and they never drift apart.
+
+const LAYERS: usize = 3;
+const NATURAL_MINOR: [i32; 7] = [0, 2, 3, 5, 7, 8, 10];
+
+struct TrackStyle {
+    root: f32, // Hz
+    bpm: f32,
+    progression: [usize; MUSIC_BARS], // scale degree of each bar's chord
+}
+
+// Levels cycle through these
+const TRACKS: [TrackStyle; 3] = [
+    TrackStyle {
+        root: 110.0,
+        bpm: 120.0,
+        progression: [0, 5, 2, 6],
+    },
+    TrackStyle {
+        root: 98.0,
+        bpm: 132.0,
+        progression: [0, 3, 4, 0],
+    },
+    TrackStyle {
+        root: 123.5,
+        bpm: 144.0,
+        progression: [0, 6, 5, 4],
+    },
+];
+
+fn note(root: f32, semitones: i32) -> f32 {
+    root * 2f32.powf(semitones as f32 / 12.0)
+}
+
+/// Semitones of the triad on a scale degree, stacked in thirds.
+fn triad(degree: usize) -> [i32; 3] {
+    [0, 2, 4].map(|step| {
+        let idx = degree + step;
+        NATURAL_MINOR[idx % 7] + 12 * (idx / 7) as i32
+    })
+}
+
+impl TrackStyle {
+    /// The samples of one layer, `MUSIC_BARS` bars long.
+    fn render_layer(&self, layer: usize) -> Vec<f32> {
+        let beat = 60.0 / self.bpm;
+        let beat_len = (beat * SAMPLE_RATE as f32) as usize;
+        let mut buffer = vec![0.0; beat_len * 4 * MUSIC_BARS];
+
+        for (bar, degree) in self.progression.iter().enumerate() {
+            let chord = triad(*degree);
+            let bar_start = bar * 4 * beat_len;
+            match layer {
+                0 => {
+                    for tone in chord {
+                        let pad =
+                            Voice::new(Wave::Sine, note(self.root, tone + 12), beat * 4.0, 0.08)
+                                .envelope(Envelope::SUSTAIN);
+                        overlay(&mut buffer, bar_start, &pad.render());
+                    }
+                    let bass =
+                        Voice::new(Wave::Triangle, note(self.root, chord[0]), beat * 0.9, 0.45)
+                            .envelope(Envelope {
+                                attack: 0.005,
+                                decay_power: 1.0,
+                            })
+                            .render();
+                    for i in 0..4 {
+                        overlay(&mut buffer, bar_start + i * beat_len, &bass);
+                    }
+                }
+                1 => {
+                    let kick = Voice::new(Wave::Sine, 150.0, 0.18, 0.7)
+                        .sweep(45.0)
+                        .render();
+                    let snare = Voice::new(Wave::Noise, 3000.0, 0.12, 0.3).render();
+                    let hat = Voice::new(Wave::Noise, 9000.0, 0.03, 0.1).render();
+                    for i in 0..4 {
+                        let at = bar_start + i * beat_len;
+                        overlay(&mut buffer, at, if i % 2 == 0 { &kick } else { &snare });
+                        overlay(&mut buffer, at + beat_len / 2, &hat);
+                    }
+                }
+                _ => {
+                    let step_len = beat_len / 4;
+                    for i in 0..16 {
+                        let tone = chord[[0, 1, 2, 1][i % 4]] + 24;
+                        let pluck =
+                            Voice::new(Wave::Square, note(self.root, tone), beat / 4.0 * 0.9, 0.15);
+                        overlay(&mut buffer, bar_start + i * step_len, &pluck.render());
+                    }
+                }
+            }
+        }
+        buffer
+    }
+}
+
+/// Per-level music with intensity layers. Silent if nothing loaded.
+#[derive(Default)]
+pub struct Music {
+    tracks: Vec<Vec<Sound>>, // layers of each track, in `TRACKS` order
+    playing: Option<usize>,
+    gains: [f32; LAYERS],
+    duck: f32,
+}
+
+impl Music {
+    /// Synthesizes every track, yielding a frame between layers. Meant to run
+    /// as a coroutine.
+    pub async fn load() -> Music {
+        let mut music = Music::default();
+        if !cfg!(feature = "audio") {
+            return music;
+        }
+
+        for style in &TRACKS {
+            let mut layers = Vec::new();
+            for layer in 0..LAYERS {
+                match load_sound_from_bytes(&encode_wav(&style.render_layer(layer))).await {
+                    Ok(sound) => layers.push(sound),
+                    Err(err) => eprintln!("Failed to load music: {}", err),
+                }
+                next_frame().await;
+            }
+            if layers.len() == LAYERS {
+                music.tracks.push(layers);
+            }
+        }
+        music
+    }
+
+    /// Follows the game: switches tracks with the level, fades layers in as
+    /// the level empties or extra balls come into play, and ducks while paused.
+    pub fn update(&mut self, game: &Game, settings: &Settings, dt: f32) {
+        if self.tracks.is_empty() {
+            return;
+        }
+
+        let track = match game.state {
+            GameState::Menu | GameState::NameEntry | GameState::HighScores => None,
+            _ => Some(game.campaign.current % self.tracks.len()),
+        };
+        if track != self.playing {
+            if let Some(old) = self.playing {
+                self.tracks[old].iter().for_each(stop_sound);
+            }
+            if let Some(new) = track {
+                for sound in &self.tracks[new] {
+                    play_sound(
+                        sound,
+                        PlaySoundParams {
+                            looped: true,
+                            volume: 0.0,
+                        },
+                    );
+                }
+            }
+            self.playing = track;
+            self.gains = [0.0; LAYERS];
+            self.duck = 1.0;
+        }
+        let Some(track) = self.playing else {
+            return;
+        };
+
+        let mut active = 1 + (game.level_progress() * LAYERS as f32) as usize;
+        if game.balls_in_play() > 1 {
+            active += 1;
+        }
+        for (layer, gain) in self.gains.iter_mut().enumerate() {
+            let target = if layer < active { 1.0 } else { 0.0 };
+            *gain = approach(*gain, target, dt / MUSIC_CROSSFADE_TIME);
+        }
+
+        let paused = matches!(
+            game.state,
+            GameState::Paused | GameState::Settings | GameState::Controls
+        );
+        let duck_target = if paused { MUSIC_PAUSE_DUCK } else { 1.0 };
+        self.duck = approach(self.duck, duck_target, dt / MUSIC_DUCK_TIME);
+
+        let volume = settings.master_volume * settings.music_volume * self.duck;
+        for (sound, gain) in self.tracks[track].iter().zip(self.gains) {
+            set_sound_volume(sound, volume * gain);
+        }
+    }
+}
+
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if value < target {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
@@ -0,0 +1,305 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::entities::{Arena, BounceModel};
+use crate::game::Game;
+use crate::systems::input::FrameInput;
+
+// --- Replay Format ---
+//
+// Little-endian binary file:
+//
+//   b"BBRP", version: u8, seed: u64, config fingerprint: u64,
+//   arena width: f32, arena height: f32
+//
+// followed by records, each starting with a tag byte:
+//
+//   0  input run: ticks: u32, move_dir: i8 (scaled by 127), launch: u8,
+//      target_x: f32 (NaN when the pointer is not steering)
+//   1  arena resize: width: f32, height: f32
+//   2  restart level (from the pause menu)
+//   3  bounce model change: model: u8 (index into `BounceModel::ALL`)
+//
+// Consecutive ticks with identical input are merged into a single run. Records
+// between two input runs are played back in the order they were written.
+
+const MAGIC: &[u8; 4] = b"BBRP";
+const VERSION: u8 = 4;
+
+const TAG_INPUT: u8 = 0;
+const TAG_ARENA: u8 = 1;
+const TAG_RESTART_LEVEL: u8 = 2;
+const TAG_BOUNCE_MODEL: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Record {
+    Input {
+        ticks: u32,
+        move_dir: i8,
+        launch: bool,
+        target_x: Option<f32>,
+    },
+    Arena(f32, f32),
+    RestartLevel,
+    BounceModel(BounceModel),
+}
+
+fn encode_move(move_dir: f32) -> i8 {
+    (move_dir.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
+
+fn decode_move(move_dir: i8) -> f32 {
+    move_dir as f32 / 127.0
+}
+
+impl FrameInput {
+    /// The input as it will read back from a replay. Ticks always run on the
+    /// quantized value so live play and playback stay bit-identical.
+    pub fn quantized(&self) -> FrameInput {
+        FrameInput {
+            move_dir: decode_move(encode_move(self.move_dir)),
+            ..*self
+        }
+    }
+}
+
+pub struct Recorder {
+    seed: u64,
+    config_fingerprint: u64,
+    arena: Arena,
+    records: Vec<Record>,
+}
+
+impl Recorder {
+    pub fn new(seed: u64, config_fingerprint: u64, arena: Arena) -> Self {
+        Recorder {
+            seed,
+            config_fingerprint,
+            arena,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn record_input(&mut self, input: &FrameInput) {
+        let move_dir = encode_move(input.move_dir);
+        if let Some(Record::Input {
+            ticks,
+            move_dir: last_move,
+            launch: last_launch,
+            target_x: last_target,
+        }) = self.records.last_mut()
+            && *last_move == move_dir
+            && *last_launch == input.launch
+            && *last_target == input.target_x
+        {
+            *ticks += 1;
+            return;
+        }
+        self.records.push(Record::Input {
+            ticks: 1,
+            move_dir,
+            launch: input.launch,
+            target_x: input.target_x,
+        });
+    }
+
+    pub fn record_arena(&mut self, arena: Arena) {
+        self.records.push(Record::Arena(arena.width, arena.height));
+    }
+
+    pub fn record_restart_level(&mut self) {
+        self.records.push(Record::RestartLevel);
+    }
+
+    pub fn record_bounce_model(&mut self, model: BounceModel) {
+        self.records.push(Record::BounceModel(model));
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.config_fingerprint.to_le_bytes());
+        bytes.extend_from_slice(&self.arena.width.to_le_bytes());
+        bytes.extend_from_slice(&self.arena.height.to_le_bytes());
+
+        for record in &self.records {
+            match *record {
+                Record::Input {
+                    ticks,
+                    move_dir,
+                    launch,
+                    target_x,
+                } => {
+                    bytes.push(TAG_INPUT);
+                    bytes.extend_from_slice(&ticks.to_le_bytes());
+                    bytes.extend_from_slice(&move_dir.to_le_bytes());
+                    bytes.push(launch as u8);
+                    bytes.extend_from_slice(&target_x.unwrap_or(f32::NAN).to_le_bytes());
+                }
+                Record::Arena(width, height) => {
+                    bytes.push(TAG_ARENA);
+                    bytes.extend_from_slice(&width.to_le_bytes());
+                    bytes.extend_from_slice(&height.to_le_bytes());
+                }
+                Record::RestartLevel => bytes.push(TAG_RESTART_LEVEL),
+                Record::BounceModel(model) => {
+                    bytes.push(TAG_BOUNCE_MODEL);
+                    bytes.push(BounceModel::ALL.iter().position(|m| *m == model).unwrap() as u8);
+                }
+            }
+        }
+
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bytes)
+    }
+}
+
+/// Something recorded between ticks, applied before the next tick's input.
+#[derive(Clone, Copy)]
+pub enum ReplayEvent {
+    Arena(Arena),
+    BounceModel(BounceModel),
+    RestartLevel,
+}
+
+impl ReplayEvent {
+    pub fn apply(self, game: &mut Game) {
+        match self {
+            ReplayEvent::Arena(arena) => game.set_arena(arena),
+            ReplayEvent::BounceModel(model) => game.set_bounce_model(model),
+            ReplayEvent::RestartLevel => game.restart_level(),
+        }
+    }
+}
+
+/// What the next tick of a replay needs: the events recorded since the last
+/// tick, to be applied in file order, then the input for the tick itself.
+pub struct ReplayStep {
+    pub events: Vec<ReplayEvent>,
+    pub input: FrameInput,
+}
+
+pub struct Replay {
+    pub seed: u64,
+    pub config_fingerprint: u64, // `Config::fingerprint` of the recording run
+    pub arena: Arena,
+    records: Vec<Record>,
+    cursor: usize,
+    used_ticks: u32,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader
+        .read_exact(&mut buf)
+        .map_err(|_| invalid("replay file is truncated"))?;
+    Ok(buf)
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let bytes = fs::read(path)?;
+        let mut reader = bytes.as_slice();
+
+        if &read_array::<4>(&mut reader)? != MAGIC {
+            return Err(invalid("not a replay file"));
+        }
+        let [version] = read_array::<1>(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid("unsupported replay version"));
+        }
+        let seed = u64::from_le_bytes(read_array(&mut reader)?);
+        let config_fingerprint = u64::from_le_bytes(read_array(&mut reader)?);
+        let arena = Arena {
+            width: f32::from_le_bytes(read_array(&mut reader)?),
+            height: f32::from_le_bytes(read_array(&mut reader)?),
+        };
+
+        let mut records = Vec::new();
+        while !reader.is_empty() {
+            let [tag] = read_array::<1>(&mut reader)?;
+            let record = match tag {
+                TAG_INPUT => Record::Input {
+                    ticks: u32::from_le_bytes(read_array(&mut reader)?),
+                    move_dir: i8::from_le_bytes(read_array(&mut reader)?),
+                    launch: read_array::<1>(&mut reader)?[0] != 0,
+                    target_x: Some(f32::from_le_bytes(read_array(&mut reader)?))
+                        .filter(|x| !x.is_nan()),
+                },
+                TAG_ARENA => Record::Arena(
+                    f32::from_le_bytes(read_array(&mut reader)?),
+                    f32::from_le_bytes(read_array(&mut reader)?),
+                ),
+                TAG_RESTART_LEVEL => Record::RestartLevel,
+                TAG_BOUNCE_MODEL => {
+                    let [model] = read_array::<1>(&mut reader)?;
+                    match BounceModel::ALL.get(model as usize) {
+                        Some(&model) => Record::BounceModel(model),
+                        None => return Err(invalid("unknown bounce model")),
+                    }
+                }
+                _ => return Err(invalid("unknown replay record")),
+            };
+            records.push(record);
+        }
+
+        Ok(Replay {
+            seed,
+            config_fingerprint,
+            arena,
+            records,
+            cursor: 0,
+            used_ticks: 0,
+        })
+    }
+
+    /// Returns the next tick's step, or `None` once the recording is exhausted.
+    pub fn next_step(&mut self) -> Option<ReplayStep> {
+        let mut events = Vec::new();
+        loop {
+            match *self.records.get(self.cursor)? {
+                Record::Arena(width, height) => {
+                    events.push(ReplayEvent::Arena(Arena { width, height }));
+                    self.cursor += 1;
+                }
+                Record::RestartLevel => {
+                    events.push(ReplayEvent::RestartLevel);
+                    self.cursor += 1;
+                }
+                Record::BounceModel(model) => {
+                    events.push(ReplayEvent::BounceModel(model));
+                    self.cursor += 1;
+                }
+                Record::Input {
+                    ticks,
+                    move_dir,
+                    launch,
+                    target_x,
+                } => {
+                    self.used_ticks += 1;
+                    if self.used_ticks >= ticks {
+                        self.cursor += 1;
+                        self.used_ticks = 0;
+                    }
+                    return Some(ReplayStep {
+                        events,
+                        input: FrameInput {
+                            move_dir: decode_move(move_dir),
+                            launch,
+                            target_x,
+                        },
+                    });
+                }
+            }
+        }
+    }
+}
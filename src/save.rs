@@ -0,0 +1,456 @@
+use ::rand::RngCore;
+use hecs::World;
+use macroquad::prelude::*;
+use std::fmt;
+use std::path::Path;
+use std::str::{FromStr, SplitWhitespace};
+
+use crate::config::Config;
+use crate::constants::*;
+use crate::entities::*;
+use crate::game::{Game, spawn_ball, spawn_paddle};
+use crate::level::Campaign;
+use crate::storage::write_atomic;
+
+// --- Save Format ---
+//
+// Plain text, one record per line, fields separated by spaces:
+//
+//   breaker_block_save <version>
+//   state <Ready|Playing|LevelComplete>
+//   score <n>          combo <n>          lives <n>          level <campaign index>
+//   speed <ball speed>
+//   seed <u64>         arena <width> <height>
+//   config <fingerprint>
+//   paddle <x> <y> <width> <height> <laser cooldown>
+//   effect <label> <seconds left>
+//   ball <x> <y> <vx> <vy> <spin> <since contact> <speed scale> [stuck <offset>]
+//   block <x> <y> <width> <height> <r> <g> <b> <a> <hits> <max> <value> <kind> [channel]
+//   capsule <x> <y> <vx> <vy> <label>
+//   laser <x> <y> <vx> <vy>
+//
+// Floats are written with their shortest exact representation, so positions
+// and velocities round-trip bit for bit. The rng is reseeded on save and the
+// new seed stored, which keeps everything after a resume deterministic. That
+// only holds under the same tuning, so a save made with another config loads
+// with a warning.
+// Particles and trails are cosmetic and not saved.
+
+const HEADER: &str = "breaker_block_save";
+const VERSION: u32 = 6;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+/// Whether the game is somewhere a save can pick up from. A paused game saves
+/// the state it was paused in.
+pub fn can_save(state: GameState) -> bool {
+    matches!(
+        state,
+        GameState::Ready
+            | GameState::Playing
+            | GameState::LevelComplete
+            | GameState::Paused
+            | GameState::Settings
+            | GameState::Controls
+    )
+}
+
+fn block_kind_name(kind: BlockKind) -> String {
+    match kind {
+        BlockKind::Normal => "normal".to_owned(),
+        BlockKind::Steel => "steel".to_owned(),
+        BlockKind::Explosive => "explosive".to_owned(),
+        BlockKind::Portal(channel) => format!("portal {}", channel),
+    }
+}
+
+pub fn save_game(game: &mut Game, path: &Path) -> std::io::Result<()> {
+    let seed = game.rng.0.next_u64();
+    game.rng = GameRng::new(seed);
+
+    let state = match game.state {
+        GameState::Paused | GameState::Settings | GameState::Controls => game.resume_state,
+        state => state,
+    };
+    let state = match state {
+        GameState::Ready => "Ready",
+        GameState::Playing => "Playing",
+        _ => "LevelComplete",
+    };
+    let mut out = format!("{} {}\n", HEADER, VERSION);
+    out += &format!("state {}\n", state);
+    out += &format!("score {}\n", game.score);
+    out += &format!("combo {}\n", game.combo);
+    out += &format!("lives {}\n", game.lives);
+    out += &format!("level {}\n", game.campaign.current);
+    out += &format!("speed {}\n", game.ball_speed);
+    out += &format!("seed {}\n", seed);
+    out += &format!("arena {} {}\n", game.arena.width, game.arena.height);
+    out += &format!("config {}\n", game.config.fingerprint());
+
+    let world = &mut game.world;
+    for (_id, (pos, rect, effects, _tag)) in
+        world.query_mut::<(&Position, &RectComp, &ActiveEffects, &PaddleTag)>()
+    {
+        out += &format!(
+            "paddle {} {} {} {} {}\n",
+            pos.0.x, pos.0.y, rect.0.x, rect.0.y, effects.laser_cooldown
+        );
+        for (kind, remaining) in &effects.timers {
+            out += &format!("effect {} {}\n", kind.label(), remaining);
+        }
+    }
+    for (_id, (pos, vel, spin, since_contact, scale, stuck, _tag)) in world.query_mut::<(
+        &Position,
+        &Velocity,
+        &Spin,
+        &SinceContact,
+        &SpeedScale,
+        Option<&StuckToPaddle>,
+        &BallTag,
+    )>() {
+        out += &format!(
+            "ball {} {} {} {} {} {} {}",
+            pos.0.x, pos.0.y, vel.0.x, vel.0.y, spin.0, since_contact.0, scale.0
+        );
+        if let Some(stuck) = stuck {
+            out += &format!(" stuck {}", stuck.0);
+        }
+        out += "\n";
+    }
+    for (_id, (pos, rect, color, health, kind, value, _tag)) in world.query_mut::<(
+        &Position,
+        &RectComp,
+        &ColorComp,
+        &Health,
+        &BlockKind,
+        &ScoreValue,
+        &BlockTag,
+    )>() {
+        out += &format!(
+            "block {} {} {} {} {} {} {} {} {} {} {} {}\n",
+            pos.0.x,
+            pos.0.y,
+            rect.0.x,
+            rect.0.y,
+            color.0.r,
+            color.0.g,
+            color.0.b,
+            color.0.a,
+            health.hits,
+            health.max,
+            value.0,
+            block_kind_name(*kind)
+        );
+    }
+    for (_id, (pos, vel, power, _tag)) in
+        world.query_mut::<(&Position, &Velocity, &PowerUpComp, &CapsuleTag)>()
+    {
+        out += &format!(
+            "capsule {} {} {} {} {}\n",
+            pos.0.x,
+            pos.0.y,
+            vel.0.x,
+            vel.0.y,
+            power.0.label()
+        );
+    }
+    for (_id, (pos, vel, _tag)) in world.query_mut::<(&Position, &Velocity, &LaserTag)>() {
+        out += &format!("laser {} {} {} {}\n", pos.0.x, pos.0.y, vel.0.x, vel.0.y);
+    }
+
+    write_atomic(path, &out)
+}
+
+struct Fields<'a> {
+    tokens: SplitWhitespace<'a>,
+    line: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn error(&self, message: impl Into<String>) -> SaveError {
+        SaveError::Parse {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, SaveError> {
+        match self.tokens.next() {
+            Some(token) => Ok(token),
+            None => Err(self.error("missing field")),
+        }
+    }
+
+    fn next<T: FromStr>(&mut self) -> Result<T, SaveError> {
+        let token = self.word()?;
+        token
+            .parse()
+            .map_err(|_| self.error(format!("invalid value '{}'", token)))
+    }
+
+    fn vec2(&mut self) -> Result<Vec2, SaveError> {
+        Ok(vec2(self.next()?, self.next()?))
+    }
+
+    fn power_up(&mut self) -> Result<PowerUpKind, SaveError> {
+        let label = self.word()?;
+        PowerUpKind::ALL
+            .into_iter()
+            .find(|kind| kind.label() == label)
+            .ok_or_else(|| self.error(format!("unknown power-up '{}'", label)))
+    }
+
+    fn block_kind(&mut self) -> Result<BlockKind, SaveError> {
+        Ok(match self.word()? {
+            "normal" => BlockKind::Normal,
+            "steel" => BlockKind::Steel,
+            "explosive" => BlockKind::Explosive,
+            "portal" => BlockKind::Portal(self.next()?),
+            other => return Err(self.error(format!("unknown block kind '{}'", other))),
+        })
+    }
+
+    fn finish(mut self) -> Result<(), SaveError> {
+        match self.tokens.next() {
+            Some(extra) => Err(self.error(format!("unexpected '{}'", extra))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn spawn_record(
+    world: &mut World,
+    config: &Config,
+    record: &str,
+    fields: &mut Fields<'_>,
+) -> Result<(), SaveError> {
+    match record {
+        "paddle" => {
+            let pos = fields.vec2()?;
+            let size = fields.vec2()?;
+            let laser_cooldown = fields.next()?;
+            spawn_paddle(world, config, pos.x, pos.y);
+            for (_id, (rect, effects, _tag)) in
+                world.query_mut::<(&mut RectComp, &mut ActiveEffects, &PaddleTag)>()
+            {
+                rect.0 = size;
+                effects.laser_cooldown = laser_cooldown;
+            }
+        }
+        "effect" => {
+            let kind = fields.power_up()?;
+            let remaining = fields.next()?;
+            let mut found = false;
+            for (_id, (effects, _tag)) in world.query_mut::<(&mut ActiveEffects, &PaddleTag)>() {
+                effects.timers.push((kind, remaining));
+                found = true;
+            }
+            if !found {
+                return Err(fields.error("effect before paddle"));
+            }
+        }
+        "ball" => {
+            let pos = fields.vec2()?;
+            let vel = fields.vec2()?;
+            let spin = fields.next()?;
+            let since_contact = fields.next()?;
+            let scale = fields.next()?;
+            let ball = spawn_ball(world, config, pos.x, pos.y);
+            if let Ok((velocity, ball_spin, ball_since_contact, ball_scale)) = world
+                .query_one_mut::<(&mut Velocity, &mut Spin, &mut SinceContact, &mut SpeedScale)>(
+                    ball,
+                )
+            {
+                velocity.0 = vel;
+                ball_spin.0 = spin;
+                ball_since_contact.0 = since_contact;
+                ball_scale.0 = scale;
+            }
+            if let Some(token) = fields.tokens.next() {
+                if token != "stuck" {
+                    return Err(fields.error(format!("unexpected '{}'", token)));
+                }
+                let offset = fields.next()?;
+                let _ = world.insert_one(ball, StuckToPaddle(offset));
+            }
+        }
+        "block" => {
+            let pos = fields.vec2()?;
+            let size = fields.vec2()?;
+            let color = Color::new(
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+            );
+            let health = Health {
+                hits: fields.next()?,
+                max: fields.next()?,
+            };
+            let value = ScoreValue(fields.next()?);
+            let kind = fields.block_kind()?;
+            world.spawn((
+                Position(pos),
+                RectComp(size),
+                ColorComp(color),
+                health,
+                kind,
+                value,
+                BlockTag,
+            ));
+        }
+        "capsule" => {
+            let pos = fields.vec2()?;
+            let vel = fields.vec2()?;
+            let kind = fields.power_up()?;
+            world.spawn((
+                Position(pos),
+                PrevPosition(pos),
+                Velocity(vel),
+                RectComp(vec2(CAPSULE_WIDTH, CAPSULE_HEIGHT)),
+                PowerUpComp(kind),
+                CapsuleTag,
+            ));
+        }
+        "laser" => {
+            let pos = fields.vec2()?;
+            let vel = fields.vec2()?;
+            world.spawn((
+                Position(pos),
+                PrevPosition(pos),
+                Velocity(vel),
+                RectComp(vec2(4.0, 14.0)),
+                LaserTag,
+            ));
+        }
+        other => return Err(fields.error(format!("unknown record '{}'", other))),
+    }
+    Ok(())
+}
+
+/// Restores a game written by `save_game` on top of `campaign`.
+pub fn load_game(path: &Path, mut campaign: Campaign, config: Config) -> Result<Game, SaveError> {
+    let source = std::fs::read_to_string(path)?;
+    let mut lines = source.lines().enumerate().map(|(idx, line)| Fields {
+        tokens: line.split_whitespace(),
+        line: idx + 1,
+    });
+
+    let mut header = lines.next().ok_or(SaveError::Parse {
+        line: 1,
+        message: "empty save file".to_owned(),
+    })?;
+    if header.word()? != HEADER {
+        return Err(header.error("not a save file"));
+    }
+    if header.next::<u32>()? != VERSION {
+        return Err(header.error("unsupported save version"));
+    }
+    header.finish()?;
+
+    let mut state = GameState::Ready;
+    let mut score = 0;
+    let mut combo = 0;
+    let mut lives = config.game.starting_lives;
+    let mut ball_speed = None;
+    let mut seed = 0;
+    let mut arena = Arena {
+        width: ARENA_WIDTH,
+        height: ARENA_HEIGHT,
+    };
+    let mut world = World::new();
+
+    for mut fields in lines {
+        let Some(record) = fields.tokens.next() else {
+            continue;
+        };
+        match record {
+            "state" => {
+                state = match fields.word()? {
+                    "Ready" => GameState::Ready,
+                    "Playing" => GameState::Playing,
+                    "LevelComplete" => GameState::LevelComplete,
+                    other => return Err(fields.error(format!("unknown state '{}'", other))),
+                }
+            }
+            "score" => score = fields.next()?,
+            "combo" => combo = fields.next()?,
+            "lives" => lives = fields.next()?,
+            "level" => {
+                let level: usize = fields.next()?;
+                if level >= campaign.levels.len() {
+                    return Err(fields.error(format!("level {} is not in the campaign", level)));
+                }
+                campaign.current = level;
+            }
+            "speed" => ball_speed = Some(fields.next()?),
+            "seed" => seed = fields.next()?,
+            "config" => {
+                let fingerprint: u64 = fields.next()?;
+                if fingerprint != config.fingerprint() {
+                    eprintln!(
+                        "Save {} was made with a different config; it resumes under the current one",
+                        path.display()
+                    );
+                }
+            }
+            "arena" => {
+                arena = Arena {
+                    width: fields.next()?,
+                    height: fields.next()?,
+                }
+            }
+            _ => spawn_record(&mut world, &config, record, &mut fields)?,
+        }
+        fields.finish()?;
+    }
+
+    let paddles = world.query_mut::<&PaddleTag>().into_iter().count();
+    if paddles != 1 {
+        return Err(SaveError::Parse {
+            line: source.lines().count(),
+            message: format!("expected one paddle, found {}", paddles),
+        });
+    }
+
+    let blocks_left = world
+        .query_mut::<(&BlockKind, &BlockTag)>()
+        .into_iter()
+        .filter(|(_id, (kind, _tag))| kind.is_destructible())
+        .count();
+
+    let mut game = Game::new(campaign, arena, seed, config);
+    game.world = world;
+    game.grid.rebuild(&mut game.world, arena);
+    game.blocks_left = blocks_left;
+    game.state = state;
+    game.score = score;
+    game.combo = combo;
+    game.lives = lives;
+    if let Some(ball_speed) = ball_speed {
+        game.ball_speed = ball_speed;
+    }
+    Ok(game)
+}
@@ -0,0 +1,136 @@
+use macroquad::prelude::*;
+
+use crate::bindings::{Action, Controls};
+use crate::constants::*;
+use crate::highscores::HighScores;
+
+/// Draws `text` horizontally centred on the screen with its baseline at `y`.
+pub fn draw_centered(text: &str, y: f32, size: f32, color: Color) {
+    let text_size = measure_text(text, None, size as u16, 1.0);
+    draw_text(
+        text,
+        screen_width() / 2.0 - text_size.width / 2.0,
+        y,
+        size,
+        color,
+    );
+}
+
+pub fn draw_name_entry(score: i32, name: &str, controls: &Controls) {
+    let center = screen_height() / 2.0;
+    draw_centered("NEW HIGH SCORE!", center - 100.0, 80.0, GOLD);
+    draw_centered(&format!("SCORE: {}", score), center - 40.0, 40.0, WHITE);
+    draw_centered("ENTER YOUR NAME", center + 20.0, 30.0, LIGHTGRAY);
+
+    // Blinking cursor while there is room for more characters
+    let cursor = if name.chars().count() < MAX_NAME_LENGTH && get_time().fract() < 0.5 {
+        "_"
+    } else {
+        " "
+    };
+    draw_centered(&format!("{}{}", name, cursor), center + 80.0, 50.0, SKYBLUE);
+    draw_centered(
+        &format!("PRESS {} TO SAVE", controls.prompt(Action::Confirm)),
+        center + 130.0,
+        30.0,
+        WHITE,
+    );
+}
+
+pub fn draw_high_scores(high_scores: &HighScores, highlight: Option<usize>, controls: &Controls) {
+    let top = screen_height() / 2.0 - 250.0;
+    draw_centered("HIGH SCORES", top, 80.0, SKYBLUE);
+
+    if high_scores.entries.is_empty() {
+        draw_centered("NO SCORES YET", top + 120.0, 30.0, LIGHTGRAY);
+    }
+
+    let left = screen_width() / 2.0 - 300.0;
+    for (rank, entry) in high_scores.entries.iter().enumerate() {
+        let y = top + 70.0 + rank as f32 * 36.0;
+        let color = if highlight == Some(rank) { GOLD } else { WHITE };
+        draw_text(&format!("{:>2}.", rank + 1), left, y, 30.0, color);
+        draw_text(&entry.name, left + 50.0, y, 30.0, color);
+        draw_text(&entry.score.to_string(), left + 270.0, y, 30.0, color);
+        draw_text(&format!("LV {}", entry.level), left + 380.0, y, 30.0, color);
+        draw_text(&entry.date, left + 460.0, y, 30.0, color);
+    }
+
+    draw_centered(
+        &format!("PRESS {} TO RETURN", controls.prompt(Action::Confirm)),
+        top + 120.0 + MAX_HIGH_SCORES as f32 * 36.0,
+        30.0,
+        WHITE,
+    );
+}
+
+/// A vertical list of options, navigated with the menu actions or the mouse.
+#[derive(Default)]
+pub struct MenuList {
+    pub selected: usize,
+}
+
+impl MenuList {
+    /// Draws `items` centred from `top` down and returns the index of the item
+    /// activated this frame, if any.
+    pub fn show(&mut self, items: &[String], top: f32, controls: &Controls) -> Option<usize> {
+        if items.is_empty() {
+            return None;
+        }
+        let count = items.len();
+        self.selected = self.selected.min(count - 1);
+
+        if controls.pressed(Action::MenuUp) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if controls.pressed(Action::MenuDown) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let mut activated = (controls.pressed(Action::Confirm) || controls.pressed(Action::Launch))
+            .then_some(self.selected);
+
+        // Hovering only takes the selection when the mouse actually moves, so a
+        // resting cursor does not fight the keyboard
+        let mouse = Vec2::from(mouse_position());
+        let mouse_moved = mouse_delta_position() != Vec2::ZERO;
+        for (i, item) in items.iter().enumerate() {
+            let y = top + i as f32 * MENU_ITEM_SPACING;
+            let text_size = measure_text(item, None, 40, 1.0);
+            let bounds = Rect::new(
+                screen_width() / 2.0 - text_size.width / 2.0 - 20.0,
+                y - text_size.offset_y - 10.0,
+                text_size.width + 40.0,
+                text_size.height + 20.0,
+            );
+            if bounds.contains(mouse) {
+                if mouse_moved {
+                    self.selected = i;
+                }
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    self.selected = i;
+                    activated = Some(i);
+                }
+            }
+
+            let color = if i == self.selected {
+                draw_rectangle_lines(bounds.x, bounds.y, bounds.w, bounds.h, 2.0, GOLD);
+                GOLD
+            } else {
+                WHITE
+            };
+            draw_centered(item, y, 40.0, color);
+        }
+        activated
+    }
+}
+
+/// Darkens whatever was drawn so far, for overlays on top of the playfield.
+pub fn dim_screen() {
+    draw_rectangle(
+        0.0,
+        0.0,
+        screen_width(),
+        screen_height(),
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+}
@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use crate::constants::*;
+use crate::entities::BounceModel;
+use crate::storage::{data_dir, write_atomic};
+
+// --- Settings File ---
+//
+// `key = value` lines. Unknown keys and bad values are reported and ignored,
+// so a hand-edited file never stops the game from starting.
+
+/// Which devices steer the paddle. Hybrid follows whichever was used last.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ControlMode {
+    Keyboard,
+    Mouse,
+    Hybrid,
+}
+
+impl ControlMode {
+    pub const ALL: [ControlMode; 3] = [
+        ControlMode::Keyboard,
+        ControlMode::Mouse,
+        ControlMode::Hybrid,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlMode::Keyboard => "keyboard",
+            ControlMode::Mouse => "mouse",
+            ControlMode::Hybrid => "hybrid",
+        }
+    }
+
+    pub fn next(self) -> ControlMode {
+        let idx = ControlMode::ALL
+            .iter()
+            .position(|mode| *mode == self)
+            .unwrap_or_default();
+        ControlMode::ALL[(idx + 1) % ControlMode::ALL.len()]
+    }
+
+    fn parse(value: &str) -> Option<ControlMode> {
+        ControlMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == value)
+    }
+
+    pub fn uses_keyboard(self) -> bool {
+        self != ControlMode::Mouse
+    }
+
+    pub fn uses_pointer(self) -> bool {
+        self != ControlMode::Keyboard
+    }
+}
+
+pub struct Settings {
+    pub screen_shake: bool,
+    pub fullscreen: bool,
+    pub control_mode: ControlMode,
+    pub pointer_smoothing: bool,
+    pub pointer_speed_cap: bool,
+    pub master_volume: f32, // 0.0 to 1.0
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub bounce_model: BounceModel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            screen_shake: true,
+            fullscreen: false,
+            control_mode: ControlMode::Hybrid,
+            pointer_smoothing: true,
+            pointer_speed_cap: false,
+            master_volume: 0.8,
+            sfx_volume: 1.0,
+            music_volume: 0.6,
+            bounce_model: BounceModel::Classic,
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    data_dir().join(SETTINGS_FILE)
+}
+
+fn parse_volume(value: &str) -> Option<f32> {
+    value
+        .parse()
+        .ok()
+        .filter(|volume| (0.0..=1.0).contains(volume))
+}
+
+/// Steps a volume up by 10%, wrapping from full back to silent.
+pub fn next_volume(volume: f32) -> f32 {
+    let step = (volume * 10.0).round() + 1.0;
+    if step > 10.0 { 0.0 } else { step / 10.0 }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "on" | "yes" => Some(true),
+        "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let path = settings_path();
+        let mut settings = Settings::default();
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return settings,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", path.display(), err);
+                return settings;
+            }
+        };
+
+        for (line_idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("{}:{}: expected key = value", path.display(), line_idx + 1);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let applied = match key {
+                "screen_shake" => parse_bool(value).map(|value| settings.screen_shake = value),
+                "fullscreen" => parse_bool(value).map(|value| settings.fullscreen = value),
+                "control_mode" => {
+                    ControlMode::parse(value).map(|mode| settings.control_mode = mode)
+                }
+                "pointer_smoothing" => {
+                    parse_bool(value).map(|value| settings.pointer_smoothing = value)
+                }
+                "pointer_speed_cap" => {
+                    parse_bool(value).map(|value| settings.pointer_speed_cap = value)
+                }
+                "master_volume" => parse_volume(value).map(|value| settings.master_volume = value),
+                "sfx_volume" => parse_volume(value).map(|value| settings.sfx_volume = value),
+                "music_volume" => parse_volume(value).map(|value| settings.music_volume = value),
+                "bounce_model" => {
+                    BounceModel::parse(value).map(|model| settings.bounce_model = model)
+                }
+                _ => {
+                    eprintln!(
+                        "{}:{}: unknown setting '{}'",
+                        path.display(),
+                        line_idx + 1,
+                        key
+                    );
+                    continue;
+                }
+            };
+            if applied.is_none() {
+                eprintln!(
+                    "{}:{}: invalid value '{}' for {}",
+                    path.display(),
+                    line_idx + 1,
+                    value,
+                    key
+                );
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let mut contents = String::new();
+        contents += &format!("screen_shake = {}\n", self.screen_shake);
+        contents += &format!("fullscreen = {}\n", self.fullscreen);
+        contents += &format!("control_mode = {}\n", self.control_mode.name());
+        contents += &format!("pointer_smoothing = {}\n", self.pointer_smoothing);
+        contents += &format!("pointer_speed_cap = {}\n", self.pointer_speed_cap);
+        contents += &format!("master_volume = {}\n", self.master_volume);
+        contents += &format!("sfx_volume = {}\n", self.sfx_volume);
+        contents += &format!("music_volume = {}\n", self.music_volume);
+        contents += &format!("bounce_model = {}\n", self.bounce_model.name());
+        let path = settings_path();
+        if let Err(err) = write_atomic(&path, &contents) {
+            eprintln!("Failed to save {}: {}", path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "breaker_block";
+
+/// Per-user directory for persistent files (high scores, saves, settings).
+/// Falls back to the working directory when no home directory is known.
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    };
+    base.unwrap_or_default().join(APP_DIR)
+}
+
+/// Writes `contents` next to `path` first and renames it into place, so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(tmp, path)
+}
@@ -0,0 +1,235 @@
+// --- Synthesizer ---
+//
+// Just enough to build the sound effects in code: one oscillator per voice
+// with a frequency sweep and an envelope, rendered to mono sample buffers
+// that can be mixed and strung together.
+
+pub const SAMPLE_RATE: u32 = 22_050;
+
+#[derive(Clone, Copy)]
+pub enum Wave {
+    Sine,
+    Square,
+    Triangle,
+    Noise, // frequency sets how often a new random value is picked
+}
+
+/// Linear attack, then a decay that reaches silence at the end of the voice.
+/// Higher `decay_power` falls off faster.
+#[derive(Clone, Copy)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay_power: f32,
+}
+
+impl Envelope {
+    pub const PLUCK: Envelope = Envelope {
+        attack: 0.002,
+        decay_power: 2.0,
+    };
+    pub const SUSTAIN: Envelope = Envelope {
+        attack: 0.005,
+        decay_power: 0.5,
+    };
+
+    fn gain(&self, t: f32, duration: f32) -> f32 {
+        let attack = if self.attack > 0.0 {
+            (t / self.attack).min(1.0)
+        } else {
+            1.0
+        };
+        attack * (1.0 - t / duration).max(0.0).powf(self.decay_power)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Voice {
+    pub wave: Wave,
+    pub start_freq: f32,
+    pub end_freq: f32, // swept exponentially from start_freq
+    pub duration: f32,
+    pub volume: f32,
+    pub envelope: Envelope,
+}
+
+impl Voice {
+    pub fn new(wave: Wave, freq: f32, duration: f32, volume: f32) -> Self {
+        Voice {
+            wave,
+            start_freq: freq,
+            end_freq: freq,
+            duration,
+            volume,
+            envelope: Envelope::PLUCK,
+        }
+    }
+
+    pub fn sweep(mut self, end_freq: f32) -> Self {
+        self.end_freq = end_freq;
+        self
+    }
+
+    pub fn envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn render(&self) -> Vec<f32> {
+        let rate = SAMPLE_RATE as f32;
+        let len = (self.duration * rate) as usize;
+        let mut phase = 0.0f32;
+        let mut noise = Noise(0x9E37_79B9);
+        let mut held = noise.next();
+
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / rate;
+                let freq =
+                    self.start_freq * (self.end_freq / self.start_freq).powf(t / self.duration);
+                phase += freq / rate;
+                if phase >= 1.0 {
+                    phase -= 1.0;
+                    held = noise.next();
+                }
+
+                let value = match self.wave {
+                    Wave::Sine => (phase * std::f32::consts::TAU).sin(),
+                    Wave::Square => {
+                        if phase < 0.5 {
+                            1.0
+                        } else {
+                            -1.0
+                        }
+                    }
+                    Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                    Wave::Noise => held,
+                };
+                value * self.volume * self.envelope.gain(t, self.duration)
+            })
+            .collect()
+    }
+}
+
+/// Deterministic xorshift noise, so the same voice always renders the same samples.
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// Plays the voices together, clipped to [-1, 1].
+pub fn mix(voices: &[Voice]) -> Vec<f32> {
+    let mut out: Vec<f32> = Vec::new();
+    for voice in voices {
+        let samples = voice.render();
+        if samples.len() > out.len() {
+            out.resize(samples.len(), 0.0);
+        }
+        for (acc, sample) in out.iter_mut().zip(samples) {
+            *acc += sample;
+        }
+    }
+    for sample in &mut out {
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    out
+}
+
+/// Plays the voices one after another.
+pub fn sequence(voices: &[Voice]) -> Vec<f32> {
+    voices.iter().flat_map(Voice::render).collect()
+}
+
+/// Adds the samples into a looping buffer starting at `offset`. Whatever runs
+/// past the end wraps around to the start, so loops join without a click.
+pub fn overlay(buffer: &mut [f32], offset: usize, samples: &[f32]) {
+    if buffer.is_empty() {
+        return;
+    }
+    for (i, sample) in samples.iter().enumerate() {
+        let idx = (offset + i) % buffer.len();
+        buffer[idx] += sample;
+    }
+}
+
+/// 16-bit mono PCM WAV, the format the mixer takes sounds in.
+pub fn encode_wav(samples: &[f32]) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_length_matches_duration() {
+        let voice = Voice::new(Wave::Sine, 440.0, 0.25, 0.5);
+        assert_eq!(voice.render().len(), (0.25 * SAMPLE_RATE as f32) as usize);
+
+        let short = Voice::new(Wave::Square, 220.0, 0.1, 0.5);
+        assert_eq!(sequence(&[voice, short]).len(), 5512 + 2205);
+        assert_eq!(mix(&[voice, short]).len(), 5512);
+    }
+
+    #[test]
+    fn mix_clips_to_unit_range() {
+        let loud = Voice::new(Wave::Square, 110.0, 0.2, 0.9).envelope(Envelope::SUSTAIN);
+        let noise = Voice::new(Wave::Noise, 4000.0, 0.2, 0.9);
+        let samples = mix(&[loud, loud, noise]);
+        assert!(samples.iter().all(|s| (-1.0..=1.0).contains(s)));
+        assert!(
+            samples.iter().any(|s| s.abs() == 1.0),
+            "mix never reached the clip"
+        );
+    }
+
+    #[test]
+    fn noise_is_deterministic() {
+        let voice = Voice::new(Wave::Noise, 3000.0, 0.1, 1.0).sweep(500.0);
+        assert_eq!(voice.render(), voice.render());
+
+        let (mut a, mut b) = (Noise(7), Noise(7));
+        for _ in 0..100 {
+            let value = a.next();
+            assert_eq!(value, b.next());
+            assert!((-1.0..=1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn wav_header_sizes_match_data() {
+        let samples = Voice::new(Wave::Triangle, 330.0, 0.05, 0.8).render();
+        let bytes = encode_wav(&samples);
+        let field = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(bytes.len(), 44 + samples.len() * 2);
+        assert_eq!(field(4) as usize, bytes.len() - 8);
+        assert_eq!(field(24), SAMPLE_RATE);
+        assert_eq!(field(40) as usize, samples.len() * 2);
+    }
+}
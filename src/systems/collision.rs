@@ -1,166 +1,942 @@
+use crate::config::Config;
 use crate::constants::*;
 use crate::entities::*;
-use hecs::World;
+use crate::grid::BlockGrid;
+use hecs::{Entity, World};
 use macroquad::prelude::*;
+use std::collections::HashMap;
 
 pub struct CollisionEvents {
     pub screenshake_time: f32,
     pub score_delta: i32,
-    pub game_over: bool,
-    pub blocks_left: usize,
+    pub ball_lost: bool,    // the last ball in play fell out
+    pub blocks_left: usize, // carried between ticks, counted down as blocks break
+    pub events: Vec<GameEvent>,
+    pub combo: u32, // block hits since a ball last touched the paddle, carried between ticks
 }
 
-pub fn collision_system(world: &mut World, events: &mut CollisionEvents) {
-    let mut to_destroy = Vec::new();
-    let mut particles_to_spawn = Vec::new();
+struct BlockData {
+    id: Entity,
+    pos: Vec2,
+    size: Vec2,
+    color: Color,
+    hits: u32,
+    kind: BlockKind,
+    value: i32,
+}
 
-    // Collect paddle data
-    let mut paddles = Vec::new();
-    for (id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &PaddleTag)>() {
-        paddles.push((id, pos.0, rect.0));
+struct PaddleData {
+    pos: Vec2,
+    size: Vec2,
+    push: f32, // sideways speed handed to the ball, never above the keyboard speed
+    sticky: bool,
+}
+
+/// Blocks read from the world the first time a grid query reaches them this
+/// tick. Damage is tracked here and written back once collisions are done.
+#[derive(Default)]
+struct LoadedBlocks {
+    data: Vec<BlockData>,
+    index: HashMap<Entity, usize>,
+    found: Vec<Entity>,
+}
+
+impl LoadedBlocks {
+    fn load(&mut self, world: &mut World, id: Entity) -> Option<usize> {
+        if let Some(&i) = self.index.get(&id) {
+            return Some(i);
+        }
+        let (pos, rect, color, health, kind, value) = world
+            .query_one_mut::<(
+                &Position,
+                &RectComp,
+                &ColorComp,
+                &Health,
+                &BlockKind,
+                &ScoreValue,
+            )>(id)
+            .ok()?;
+        self.data.push(BlockData {
+            id,
+            pos: pos.0,
+            size: rect.0,
+            color: color.0,
+            hits: health.hits,
+            kind: *kind,
+            value: value.0,
+        });
+        self.index.insert(id, self.data.len() - 1);
+        Some(self.data.len() - 1)
     }
 
-    // Collect block data
-    let mut blocks = Vec::new();
-    events.blocks_left = 0;
-    for (id, (pos, rect, color, _tag)) in
-        world.query_mut::<(&Position, &RectComp, &ColorComp, &BlockTag)>()
-    {
-        blocks.push((id, pos.0, rect.0, color.0));
-        events.blocks_left += 1;
+    /// Indices of the standing blocks in the grid cells overlapping `min..max`.
+    fn near(&mut self, world: &mut World, grid: &BlockGrid, min: Vec2, max: Vec2) -> Vec<usize> {
+        let mut found = std::mem::take(&mut self.found);
+        grid.query(min, max, &mut found);
+        let mut nearby = Vec::new();
+        for id in &found {
+            if let Some(i) = self.load(world, *id)
+                && self.data[i].hits > 0
+            {
+                nearby.push(i);
+            }
+        }
+        self.found = found;
+        nearby
     }
 
-    // Ball-Wall and Ball-Paddle and Ball-Block
-    for (_ball_id, (ball_pos, ball_vel, _ball_tag)) in
-        world.query_mut::<(&mut Position, &mut Velocity, &BallTag)>()
-    {
-        // Wall collisions
-        if ball_pos.0.x - BALL_RADIUS < 0.0 {
-            ball_pos.0.x = BALL_RADIUS;
-            ball_vel.0.x *= -1.0;
-        } else if ball_pos.0.x + BALL_RADIUS > screen_width() {
-            ball_pos.0.x = screen_width() - BALL_RADIUS;
-            ball_vel.0.x *= -1.0;
+    /// The other portal on the block's channel, if it is still standing.
+    fn twin(&mut self, world: &mut World, i: usize) -> Option<usize> {
+        let BlockKind::Portal(channel) = self.data[i].kind else {
+            return None;
+        };
+        let id = self.data[i].id;
+        let twin = world
+            .query_mut::<(&BlockKind, &BlockTag)>()
+            .into_iter()
+            .find(|(other, (kind, _tag))| *other != id && **kind == BlockKind::Portal(channel))
+            .map(|(other, _)| other)?;
+        self.load(world, twin)
+    }
+}
+
+// Changes gathered while the world is borrowed by queries, applied afterwards
+#[derive(Default)]
+struct Pending {
+    to_destroy: Vec<Entity>,
+    explosions: Vec<usize>,
+    particles: Vec<(Vec2, Vec2, Color)>,
+    capsules: Vec<(Vec2, PowerUpKind)>,
+    popups: Vec<(Vec2, i32, i32)>, // (position, points, multiplier)
+}
+
+fn push_burst(
+    rng: &mut GameRng,
+    particles: &mut Vec<(Vec2, Vec2, Color)>,
+    pos: Vec2,
+    color: Color,
+    (min_count, max_count): (i32, i32),
+) {
+    for _ in 0..rng.gen_range(min_count, max_count) {
+        let is_spark = rng.gen_range(0, 5) == 0;
+        let p_color = if is_spark {
+            if rng.gen_range(0, 2) == 0 {
+                WHITE
+            } else {
+                YELLOW
+            }
+        } else {
+            color
+        };
+
+        particles.push((
+            pos,
+            vec2(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0)).normalize()
+                * rng.gen_range(100.0, 250.0),
+            p_color,
+        ));
+    }
+}
+
+fn combo_multiplier(config: &Config, combo: u32) -> i32 {
+    let scoring = &config.scoring;
+    (1 + (combo / scoring.combo_hits_per_step) as i32).min(scoring.combo_max_multiplier)
+}
+
+/// Scores a block hit at the current combo multiplier and extends the combo.
+fn award(
+    config: &Config,
+    points: i32,
+    point: Vec2,
+    pending: &mut Pending,
+    events: &mut CollisionEvents,
+) {
+    let multiplier = combo_multiplier(config, events.combo);
+    events.combo += 1;
+    events.score_delta = events
+        .score_delta
+        .saturating_add(points.saturating_mul(multiplier));
+    pending.popups.push((point, points, multiplier));
+}
+
+fn destroy_block(
+    config: &Config,
+    blocks: &mut [BlockData],
+    i: usize,
+    point: Vec2,
+    pending: &mut Pending,
+    rng: &mut GameRng,
+    events: &mut CollisionEvents,
+) {
+    let block = &mut blocks[i];
+    block.hits = 0;
+    pending.to_destroy.push(block.id);
+    award(config, block.value, point, pending, events);
+    events
+        .events
+        .push(GameEvent::BlockDestroyed { color: block.color });
+    push_burst(
+        rng,
+        &mut pending.particles,
+        point,
+        block.color,
+        config.effects.destroy_particles,
+    );
+
+    if block.kind == BlockKind::Explosive {
+        pending.explosions.push(i);
+    }
+    if rng.gen_range(0.0, 1.0) < config.powerups.drop_chance {
+        let kind = PowerUpKind::ALL[rng.gen_range(0, PowerUpKind::ALL.len())];
+        pending.capsules.push((block.pos + block.size / 2.0, kind));
+    }
+}
+
+fn hit_block(
+    config: &Config,
+    blocks: &mut [BlockData],
+    i: usize,
+    point: Vec2,
+    pending: &mut Pending,
+    rng: &mut GameRng,
+    events: &mut CollisionEvents,
+) {
+    let block = &mut blocks[i];
+    if !block.kind.is_destructible() {
+        // Steel (and unpaired portals) only deflect
+        events
+            .events
+            .push(GameEvent::BlockHit { color: block.color });
+        push_burst(
+            rng,
+            &mut pending.particles,
+            point,
+            WHITE,
+            config.effects.deflect_particles,
+        );
+        return;
+    }
+
+    block.hits -= 1;
+    if block.hits == 0 {
+        destroy_block(config, blocks, i, point, pending, rng, events);
+    } else {
+        award(config, config.scoring.hit, point, pending, events);
+        events
+            .events
+            .push(GameEvent::BlockHit { color: block.color });
+        push_burst(
+            rng,
+            &mut pending.particles,
+            point,
+            block.color,
+            config.effects.hit_particles,
+        );
+    }
+}
+
+// --- Swept Collision ---
+
+#[derive(Clone, Copy)]
+enum Target {
+    Wall,
+    Paddle(usize),
+    Block(usize),
+}
+
+struct Contact {
+    time: f32,
+    normal: Vec2, // out of the surface that was hit
+    target: Target,
+}
+
+/// Earliest time in `[0, max_time]` at which a circle leaving `start` with
+/// velocity `vel` touches the rectangle, with the contact normal. Contacts the
+/// circle is already moving away from do not count.
+fn sweep_circle_rect(
+    start: Vec2,
+    vel: Vec2,
+    radius: f32,
+    min: Vec2,
+    max: Vec2,
+    max_time: f32,
+) -> Option<(f32, Vec2)> {
+    // A circle starting inside the rect (a ball let out of a portal) has
+    // nothing to hit until it is clear, and its corners would only catch it
+    // from the inside on the way out
+    if start.cmpge(min).all() && start.cmple(max).all() {
+        return None;
+    }
+
+    let mut best: Option<(f32, Vec2)> = None;
+    let mut consider = |time: f32, normal: Vec2| {
+        if (0.0..=max_time).contains(&time)
+            && vel.dot(normal) < 0.0
+            && best.is_none_or(|(best_time, _)| time < best_time)
+        {
+            best = Some((time, normal));
         }
+    };
 
-        // Flipped Y Logic (0 is bottom, height is top)
-        if ball_pos.0.y + BALL_RADIUS > screen_height() {
-            ball_pos.0.y = screen_height() - BALL_RADIUS;
-            ball_vel.0.y *= -1.0;
+    // Sides: the circle's leading edge crosses the side's line within its span
+    for axis in 0..2 {
+        if vel[axis] == 0.0 {
+            continue;
         }
+        let (line, sign) = if vel[axis] > 0.0 {
+            (min[axis] - radius, -1.0)
+        } else {
+            (max[axis] + radius, 1.0)
+        };
+        let time = (line - start[axis]) / vel[axis];
+        let other = 1 - axis;
+        let along = start[other] + vel[other] * time;
+        if (min[other]..=max[other]).contains(&along) {
+            let mut normal = Vec2::ZERO;
+            normal[axis] = sign;
+            consider(time, normal);
+        }
+    }
 
-        if ball_pos.0.y - BALL_RADIUS + 50.0 < 0.0 {
-            events.game_over = true;
-            events.screenshake_time = 0.3;
+    // Corners: the centre's path against a circle of the ball's radius around each corner
+    let a = vel.length_squared();
+    if a == 0.0 {
+        return best;
+    }
+    for corner in [min, vec2(max.x, min.y), vec2(min.x, max.y), max] {
+        let to_start = start - corner;
+        let c = to_start.length_squared() - radius * radius;
+        let b = to_start.dot(vel);
+        let discriminant = b * b - a * c;
+        if c < 0.0 || discriminant < 0.0 {
+            continue;
         }
+        let time = (-b - discriminant.sqrt()) / a;
+        consider(time, (start + vel * time - corner) / radius);
+    }
+    best
+}
 
-        // Paddle collision (Ball falling onto paddle)
-        for (_id, p_pos, p_rect) in &paddles {
-            let closest_paddle_point = vec2(
-                ball_pos.0.x.clamp(p_pos.x, p_pos.x + p_rect.x),
-                ball_pos.0.y.clamp(p_pos.y, p_pos.y + p_rect.y),
-            );
-            let dist_paddle = ball_pos.0.distance(closest_paddle_point);
+/// The first thing the ball would hit within `max_time`, if anything.
+#[allow(clippy::too_many_arguments)]
+fn earliest_contact(
+    pos: Vec2,
+    vel: Vec2,
+    radius: f32,
+    max_time: f32,
+    arena: &Arena,
+    paddles: &[PaddleData],
+    blocks: &[BlockData],
+    nearby: &[usize],
+) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    let mut consider = |time: f32, normal: Vec2, target: Target| {
+        if time <= max_time && best.as_ref().is_none_or(|best| time < best.time) {
+            best = Some(Contact {
+                time,
+                normal,
+                target,
+            });
+        }
+    };
 
-            if dist_paddle < BALL_RADIUS && ball_vel.0.y < 0.0 {
-                let collision_normal = (ball_pos.0 - closest_paddle_point).normalize_or_zero();
-                let penetration = BALL_RADIUS - dist_paddle;
-                ball_pos.0 += collision_normal * penetration;
+    // Walls. A ball left outside by a shrinking arena bounces straight away.
+    if vel.x < 0.0 {
+        let time = (radius - pos.x) / vel.x;
+        consider(time.max(0.0), vec2(1.0, 0.0), Target::Wall);
+    } else if vel.x > 0.0 {
+        let time = (arena.width - radius - pos.x) / vel.x;
+        consider(time.max(0.0), vec2(-1.0, 0.0), Target::Wall);
+    }
+    // Flipped Y Logic (0 is bottom, height is top)
+    if vel.y > 0.0 {
+        let time = (arena.height - radius - pos.y) / vel.y;
+        consider(time.max(0.0), vec2(0.0, -1.0), Target::Wall);
+    }
 
-                ball_vel.0.y *= -1.0;
-                let hit_factor = (ball_pos.0.x - (p_pos.x + p_rect.x / 2.0)) / (p_rect.x / 2.0);
-                ball_vel.0.x = hit_factor * BALL_SPEED * 1.5;
-                ball_vel.0 = ball_vel.0.normalize() * BALL_SPEED;
+    // Only a falling ball can land on the paddle
+    if vel.y < 0.0 {
+        for (p, paddle) in paddles.iter().enumerate() {
+            if let Some((time, normal)) = sweep_circle_rect(
+                pos,
+                vel,
+                radius,
+                paddle.pos,
+                paddle.pos + paddle.size,
+                max_time,
+            ) {
+                consider(time, normal, Target::Paddle(p));
             }
         }
+    }
 
-        // Block collisions
-        let mut collisions_this_substep = 0;
-        for (id, b_pos, b_rect, b_color) in &blocks {
-            if to_destroy.contains(id) {
-                continue;
+    for &i in nearby {
+        let block = &blocks[i];
+        if let Some((time, normal)) = sweep_circle_rect(
+            pos,
+            vel,
+            radius,
+            block.pos,
+            block.pos + block.size,
+            max_time,
+        ) {
+            consider(time, normal, Target::Block(i));
+        }
+    }
+    best
+}
+
+fn reflect(vel: &mut Vec2, normal: Vec2) {
+    *vel -= 2.0 * vel.dot(normal) * normal;
+}
+
+/// Sends the ball back up off the paddle, never flatter than `min_angle`.
+/// Classic keeps the ball's speed; physical may change it and records the
+/// change in `scale`, the ball's speed relative to the game's.
+fn bounce_off_paddle(
+    model: BounceModel,
+    config: &Config,
+    ball: Vec2,
+    vel: &mut Vec2,
+    spin: &mut f32,
+    scale: &mut f32,
+    paddle: &PaddleData,
+) {
+    let bounce = &config.bounce;
+    let mut speed = vel.length();
+    let heading = match model {
+        // The landing spot alone sets the angle: straight up from the middle, widest at the ends
+        BounceModel::Classic => {
+            *spin = 0.0;
+            let half = paddle.size.x / 2.0;
+            let hit_factor = ((ball.x - (paddle.pos.x + half)) / half).clamp(-1.0, 1.0);
+            let angle = hit_factor * bounce.classic_max_angle;
+            vec2(angle.sin(), angle.cos())
+        }
+        // Mirror off the face, carry some of the paddle's motion, and pick up
+        // spin from the paddle rubbing sideways under the ball. The motion
+        // carried over speeds the ball up or slows it down, within limits.
+        BounceModel::Physical => {
+            *spin = (paddle.push * bounce.spin_factor).clamp(-bounce.max_spin, bounce.max_spin);
+            let out = vec2(vel.x + paddle.push * bounce.velocity_transfer, vel.y.abs());
+            if speed > 0.0 {
+                let base = speed / *scale;
+                *scale = (*scale * out.length() / speed)
+                    .max(bounce.min_speed_scale)
+                    .min(config.ball.max_speed / base);
+                speed = base * *scale;
             }
+            out.normalize_or_zero()
+        }
+    };
+
+    let min_rise = bounce.min_angle.sin();
+    let heading = if heading.y < min_rise {
+        let side = if heading.x < 0.0 { -1.0 } else { 1.0 };
+        vec2(side * bounce.min_angle.cos(), min_rise)
+    } else {
+        heading
+    };
+    *vel = heading * speed;
+}
+
+/// Turns a stalled ball by `angle`. Flat headings are steepened and steep
+/// ones flattened, which breaks wall-to-wall and ceiling loops alike.
+fn nudge(vel: Vec2, angle: f32) -> Vec2 {
+    let steepen = vel.y.abs() < vel.x.abs();
+    // Counter-clockwise steepens when x and y share a sign
+    let ccw = (vel.x * vel.y >= 0.0) == steepen;
+    let angle = if ccw { angle } else { -angle };
+    Vec2::from_angle(angle).rotate(vel)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn collision_system(
+    world: &mut World,
+    grid: &mut BlockGrid,
+    config: &Config,
+    arena: &Arena,
+    dt: f32,
+    bounce: BounceModel,
+    rng: &mut GameRng,
+    events: &mut CollisionEvents,
+) {
+    let mut pending = Pending::default();
+    let radius = config.ball.radius;
+    let mut stuck = Vec::new();
+    let mut lost_balls = Vec::new();
+
+    // Collect paddle data. Pointer control covers a frame's movement in one
+    // tick, so its raw velocity spikes; capping it keeps the physical bounce
+    // from flinging the ball on whichever tick the pointer caught up
+    let max_push = config.paddle.speed;
+    let mut paddles = Vec::new();
+    for (_id, (pos, rect, vel, effects, _tag)) in
+        world.query_mut::<(&Position, &RectComp, &Velocity, &ActiveEffects, &PaddleTag)>()
+    {
+        paddles.push(PaddleData {
+            pos: pos.0,
+            size: rect.0,
+            push: vel.0.x.clamp(-max_push, max_push),
+            sticky: effects.is_active(PowerUpKind::Sticky),
+        });
+    }
+
+    let mut blocks = LoadedBlocks::default();
 
-            let closest_point = vec2(
-                ball_pos.0.x.clamp(b_pos.x, b_pos.x + b_rect.x),
-                ball_pos.0.y.clamp(b_pos.y, b_pos.y + b_rect.y),
+    // Balls are swept along their velocity: each iteration finds the earliest
+    // wall, paddle or block contact on the remaining path, moves the ball there
+    // and bounces it, so fast balls cannot tunnel through anything thin
+    let mut balls = Vec::new();
+    for (ball_id, (ball_pos, ball_vel, spin, since_contact, scale, _ball_tag)) in world
+        .query_mut::<(
+            &Position,
+            &Velocity,
+            &Spin,
+            &SinceContact,
+            &SpeedScale,
+            &BallTag,
+        )>()
+        .without::<&StuckToPaddle>()
+    {
+        balls.push((
+            ball_id,
+            ball_pos.0,
+            ball_vel.0,
+            spin.0,
+            since_contact.0,
+            scale.0,
+        ));
+    }
+
+    for (ball_id, mut ball_pos, mut ball_vel, mut spin, mut since_contact, mut scale) in balls {
+        // Spin curves the ball's path and wears off
+        if spin != 0.0 {
+            ball_vel = Vec2::from_angle(spin * dt).rotate(ball_vel);
+            spin *= (-config.bounce.spin_decay * dt).exp();
+        }
+
+        // A ball that has gone this long without touching the paddle or a
+        // breakable block is caught in a loop, so turn it a little
+        since_contact += dt;
+        if since_contact >= config.bounce.stall_time {
+            ball_vel = nudge(ball_vel, config.bounce.stall_nudge_angle);
+            since_contact = 0.0;
+        }
+
+        // The paddle moves too and can close in on the ball from the side.
+        // That overlap has no time of impact, so it is resolved on the spot.
+        let mut caught = false;
+        for paddle in &paddles {
+            let closest = vec2(
+                ball_pos.x.clamp(paddle.pos.x, paddle.pos.x + paddle.size.x),
+                ball_pos.y.clamp(paddle.pos.y, paddle.pos.y + paddle.size.y),
             );
-            let dist = ball_pos.0.distance(closest_point);
-
-            if dist < BALL_RADIUS {
-                to_destroy.push(*id);
-                events.score_delta += 10;
-
-                // Prepare particles
-                let particle_count = rand::gen_range(12, 50);
-                for _ in 0..particle_count {
-                    let is_spark = rand::gen_range(0, 5) == 0;
-                    let p_color = if is_spark {
-                        if rand::gen_range(0, 2) == 0 {
-                            WHITE
-                        } else {
-                            YELLOW
-                        }
-                    } else {
-                        *b_color
-                    };
-
-                    particles_to_spawn.push((
-                        closest_point,
-                        vec2(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0)).normalize()
-                            * rand::gen_range(100.0, 250.0),
-                        p_color,
-                    ));
+            let dist = ball_pos.distance(closest);
+            if dist < radius && ball_vel.y < 0.0 {
+                ball_pos += (ball_pos - closest).normalize_or_zero() * (radius - dist);
+                bounce_off_paddle(
+                    bounce,
+                    config,
+                    ball_pos,
+                    &mut ball_vel,
+                    &mut spin,
+                    &mut scale,
+                    paddle,
+                );
+                since_contact = 0.0;
+                events.events.push(GameEvent::PaddleHit);
+                events.combo = 0;
+                if paddle.sticky {
+                    stuck.push((ball_id, ball_pos.x - paddle.pos.x));
+                    caught = true;
                 }
+            }
+        }
 
-                let collision_normal = (ball_pos.0 - closest_point).normalize_or_zero();
-                let penetration = BALL_RADIUS - dist;
+        // A ball wedged between surfaces gives up the rest of the tick rather than tunnel
+        let mut remaining = if caught { 0.0 } else { dt };
+        let mut exit_portal = None;
+        for _ in 0..=MAX_BALL_BOUNCES {
+            if remaining <= 0.0 {
+                break;
+            }
+            let end = ball_pos + ball_vel * remaining;
+            let mut nearby = blocks.near(
+                world,
+                grid,
+                ball_pos.min(end) - Vec2::splat(radius),
+                ball_pos.max(end) + Vec2::splat(radius),
+            );
+            nearby.retain(|&i| Some(i) != exit_portal);
+            let Some(contact) = earliest_contact(
+                ball_pos,
+                ball_vel,
+                radius,
+                remaining,
+                arena,
+                &paddles,
+                &blocks.data,
+                &nearby,
+            ) else {
+                ball_pos = end;
+                break;
+            };
+            ball_pos += ball_vel * contact.time;
+            remaining -= contact.time;
 
-                if collision_normal == Vec2::ZERO {
-                    ball_vel.0 *= -1.0;
-                } else {
-                    ball_pos.0 += collision_normal * penetration;
-                    ball_vel.0 =
-                        ball_vel.0 - 2.0 * ball_vel.0.dot(collision_normal) * collision_normal;
+            match contact.target {
+                Target::Wall => {
+                    ball_pos.x = ball_pos.x.clamp(radius, arena.width - radius);
+                    ball_pos.y = ball_pos.y.min(arena.height - radius);
+                    reflect(&mut ball_vel, contact.normal);
+                    events.events.push(GameEvent::WallHit);
                 }
+                Target::Paddle(p) => {
+                    let paddle = &paddles[p];
+                    bounce_off_paddle(
+                        bounce,
+                        config,
+                        ball_pos,
+                        &mut ball_vel,
+                        &mut spin,
+                        &mut scale,
+                        paddle,
+                    );
+                    since_contact = 0.0;
+                    events.events.push(GameEvent::PaddleHit);
+                    events.combo = 0;
+                    if paddle.sticky {
+                        stuck.push((ball_id, ball_pos.x - paddle.pos.x));
+                        break;
+                    }
+                }
+                Target::Block(i) => {
+                    // Portals swallow the ball and spit it out of their twin, keeping its velocity.
+                    // It reappears at the twin's centre and the sweep carries it out from there.
+                    // The twin is left out for the rest of the tick, and after that the ball is
+                    // either still inside it, which the sweep ignores, or moving away from it.
+                    if let Some(twin) = blocks.twin(world, i) {
+                        exit_portal = Some(twin);
+                        let twin = &blocks.data[twin];
+                        ball_pos = twin.pos + twin.size / 2.0;
+                        continue;
+                    }
 
-                collisions_this_substep += 1;
-                if collisions_this_substep >= 2 {
-                    break;
+                    if blocks.data[i].kind.is_destructible() {
+                        since_contact = 0.0;
+                    }
+                    let point = ball_pos - contact.normal * radius;
+                    hit_block(
+                        config,
+                        &mut blocks.data,
+                        i,
+                        point,
+                        &mut pending,
+                        rng,
+                        events,
+                    );
+                    reflect(&mut ball_vel, contact.normal);
                 }
             }
         }
+
+        if let Ok((pos, vel, ball_spin, ball_since_contact, ball_scale)) = world.query_one_mut::<(
+            &mut Position,
+            &mut Velocity,
+            &mut Spin,
+            &mut SinceContact,
+            &mut SpeedScale,
+        )>(ball_id)
+        {
+            pos.0 = ball_pos;
+            vel.0 = ball_vel;
+            ball_spin.0 = spin;
+            ball_since_contact.0 = since_contact;
+            ball_scale.0 = scale;
+        }
+        if ball_pos.y - radius < -config.ball.death_line {
+            lost_balls.push(ball_id);
+            events.events.push(GameEvent::BallLost);
+        }
+    }
+
+    // Laser bolts damage the first block they overlap and vanish off the top of the screen
+    let mut bolts = Vec::new();
+    for (bolt_id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &LaserTag)>() {
+        bolts.push((bolt_id, pos.0, rect.0));
+    }
+    let mut spent_bolts = Vec::new();
+    for (bolt_id, pos, size) in bolts {
+        if pos.y > arena.height {
+            spent_bolts.push(bolt_id);
+            continue;
+        }
+
+        let nearby = blocks.near(world, grid, pos, pos + size);
+        let hit = nearby.into_iter().find(|&i| {
+            let block = &blocks.data[i];
+            pos.x < block.pos.x + block.size.x
+                && pos.x + size.x > block.pos.x
+                && pos.y < block.pos.y + block.size.y
+                && pos.y + size.y > block.pos.y
+        });
+        if let Some(i) = hit {
+            let point = vec2(pos.x + size.x / 2.0, blocks.data[i].pos.y);
+            hit_block(
+                config,
+                &mut blocks.data,
+                i,
+                point,
+                &mut pending,
+                rng,
+                events,
+            );
+            spent_bolts.push(bolt_id);
+        }
+    }
+
+    // Explosions destroy every breakable neighbour in range, chaining through other explosives
+    while let Some(i) = pending.explosions.pop() {
+        let center = blocks.data[i].pos + blocks.data[i].size / 2.0;
+        events.screenshake_time = events.screenshake_time.max(config.effects.explosion_shake);
+        events.events.push(GameEvent::Explosion);
+        push_burst(
+            rng,
+            &mut pending.particles,
+            center,
+            ORANGE,
+            config.effects.explosion_particles,
+        );
+
+        let reach = Vec2::splat(config.blocks.explosion_radius);
+        for j in blocks.near(world, grid, center - reach, center + reach) {
+            let block = &blocks.data[j];
+            let block_center = block.pos + block.size / 2.0;
+            if block.hits == 0
+                || !block.kind.is_destructible()
+                || block_center.distance(center) > config.blocks.explosion_radius
+            {
+                continue;
+            }
+            destroy_block(
+                config,
+                &mut blocks.data,
+                j,
+                block_center,
+                &mut pending,
+                rng,
+                events,
+            );
+        }
+    }
+
+    // Process damage, destruction and spawning outside of queries to avoid borrow checker issues
+    for block in &blocks.data {
+        if block.hits > 0
+            && let Ok(mut health) = world.get::<&mut Health>(block.id)
+        {
+            health.hits = block.hits;
+        }
+    }
+
+    for entity in pending.to_destroy {
+        let block = &blocks.data[blocks.index[&entity]];
+        grid.remove(entity, block.pos, block.size);
+        let _ = world.despawn(entity);
+        events.blocks_left = events.blocks_left.saturating_sub(1);
     }
 
-    // Process destruction and spawning outside of queries to avoid borrow checker issues
-    for entity in to_destroy {
+    // Losing one of several balls only removes it, losing the last one costs a life
+    let balls_in_play = world.query_mut::<&BallTag>().into_iter().count();
+    if !lost_balls.is_empty() && lost_balls.len() == balls_in_play {
+        events.ball_lost = true;
+        events.screenshake_time = config.effects.ball_lost_shake;
+    }
+    for entity in lost_balls {
         let _ = world.despawn(entity);
-        events.blocks_left -= 1;
     }
 
-    for (pos, vel, color) in particles_to_spawn {
+    for entity in spent_bolts {
+        let _ = world.despawn(entity);
+    }
+
+    for (ball_id, offset) in stuck {
+        if let Ok(mut vel) = world.get::<&mut Velocity>(ball_id) {
+            vel.0 = Vec2::ZERO;
+        }
+        let _ = world.insert_one(ball_id, StuckToPaddle(offset));
+    }
+
+    for (pos, kind) in pending.capsules {
+        let corner = pos - vec2(CAPSULE_WIDTH, CAPSULE_HEIGHT) / 2.0;
+        world.spawn((
+            Position(corner),
+            PrevPosition(corner),
+            Velocity(vec2(0.0, -config.powerups.capsule_speed)),
+            RectComp(vec2(CAPSULE_WIDTH, CAPSULE_HEIGHT)),
+            PowerUpComp(kind),
+            CapsuleTag,
+        ));
+    }
+
+    for (pos, points, multiplier) in pending.popups {
+        let (text, color) = if multiplier > 1 {
+            (format!("+{} x{}", points, multiplier), GOLD)
+        } else {
+            (format!("+{}", points), WHITE)
+        };
+        world.spawn((
+            Position(pos),
+            PrevPosition(pos),
+            Velocity(vec2(0.0, POPUP_RISE_SPEED)),
+            ColorComp(color),
+            PopupText(text),
+            ParticleLifetime(POPUP_LIFETIME),
+            PopupTag,
+        ));
+    }
+
+    for (pos, vel, color) in pending.particles {
         world.spawn((
             Position(pos),
             Velocity(vel),
             ColorComp(color),
-            ParticleLifetime(rand::gen_range(0.4, 0.8)),
-            ParticleSize(rand::gen_range(2.0, 6.0)),
-            ParticleRotation(0.0, rand::gen_range(-5.0, 5.0)),
+            ParticleLifetime(rng.gen_range(0.4, 0.8)),
+            ParticleSize(rng.gen_range(2.0, 6.0)),
+            ParticleRotation(0.0, rng.gen_range(-5.0, 5.0)),
             ParticleDamping(0.96),
             ParticleTag,
         ));
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_ignores_rect_containing_start() {
+        let (min, max) = (vec2(100.0, 100.0), vec2(200.0, 125.0));
+        // Shallow path from the centre out past a corner
+        let inside = sweep_circle_rect(vec2(150.0, 112.5), vec2(400.0, 30.0), 10.0, min, max, 1.0);
+        assert!(inside.is_none());
+
+        let outside = sweep_circle_rect(vec2(150.0, 50.0), vec2(0.0, 100.0), 10.0, min, max, 1.0);
+        let (time, normal) = outside.expect("ball below the rect should land on it");
+        assert!((time - 0.4).abs() < 1e-5);
+        assert_eq!(normal, vec2(0.0, -1.0));
+    }
 
-    // Update particles lifetime
-    let dt = get_frame_time();
-    let mut expired = Vec::new();
-    for (id, (lifetime, _tag)) in world.query_mut::<(&mut ParticleLifetime, &ParticleTag)>() {
-        lifetime.0 -= dt;
-        if lifetime.0 <= 0.0 {
-            expired.push(id);
-        }
+    fn bounce(model: BounceModel, vel: Vec2, push: f32) -> (Vec2, f32) {
+        let paddle = PaddleData {
+            pos: vec2(100.0, 50.0),
+            size: vec2(120.0, 20.0),
+            push,
+            sticky: false,
+        };
+        let (mut vel, mut spin, mut scale) = (vel, 0.0, 1.0);
+        let ball = vec2(160.0, 80.0);
+        bounce_off_paddle(
+            model,
+            &Config::default(),
+            ball,
+            &mut vel,
+            &mut spin,
+            &mut scale,
+            &paddle,
+        );
+        (vel, scale)
+    }
+
+    #[test]
+    fn physical_bounce_transfers_paddle_speed() {
+        // Paddle moving with the ball speeds it up
+        let (vel, scale) = bounce(BounceModel::Physical, vec2(0.0, -400.0), 500.0);
+        assert!(vel.x > 0.0 && vel.y > 0.0);
+        assert!((vel.length() - 400.0 * scale).abs() < 1e-3);
+        assert!(scale > 1.0);
+
+        // but never past max_speed
+        let (vel, _) = bounce(BounceModel::Physical, vec2(0.0, -590.0), 500.0);
+        assert!((vel.length() - 600.0).abs() < 1e-3);
+
+        // and moving against it slows it down, only so far
+        let (vel, scale) = bounce(BounceModel::Physical, vec2(400.0, -300.0), -800.0);
+        assert_eq!(scale, 0.75);
+        assert!((vel.length() - 375.0).abs() < 1e-3);
+
+        let (vel, scale) = bounce(BounceModel::Classic, vec2(300.0, -400.0), 500.0);
+        assert_eq!(scale, 1.0);
+        assert!((vel.length() - 500.0).abs() < 1e-3);
     }
-    for id in expired {
-        let _ = world.despawn(id);
+
+    #[test]
+    fn portal_exit_does_not_bounce_back() {
+        let config = Config::default();
+        let arena = Arena {
+            width: 800.0,
+            height: 600.0,
+        };
+        let mut world = World::new();
+        let mut grid = BlockGrid::new(arena, BROADPHASE_CELL_SIZE);
+        let size = vec2(100.0, 25.0);
+        for x in [100.0, 500.0] {
+            let pos = vec2(x, 300.0);
+            let id = world.spawn((
+                Position(pos),
+                RectComp(size),
+                ColorComp(VIOLET),
+                Health { hits: 1, max: 1 },
+                BlockKind::Portal(1),
+                ScoreValue(0),
+                BlockTag,
+            ));
+            grid.insert(id, pos, size);
+        }
+        let ball = world.spawn((
+            Position(vec2(60.0, 280.0)),
+            Velocity(vec2(400.0, 60.0)),
+            Spin(0.0),
+            SinceContact(0.0),
+            SpeedScale(1.0),
+            BallTag,
+        ));
+
+        let mut rng = GameRng::new(0);
+        let mut events = CollisionEvents {
+            screenshake_time: 0.0,
+            score_delta: 0,
+            ball_lost: false,
+            blocks_left: 0,
+            events: Vec::new(),
+            combo: 0,
+        };
+        let mut teleports = 0;
+        let mut last = vec2(60.0, 280.0);
+        for _ in 0..120 {
+            collision_system(
+                &mut world,
+                &mut grid,
+                &config,
+                &arena,
+                TICK_DT,
+                BounceModel::Classic,
+                &mut rng,
+                &mut events,
+            );
+            let pos = world.get::<&Position>(ball).unwrap().0;
+            if pos.distance(last) > 100.0 {
+                teleports += 1;
+            }
+            last = pos;
+        }
+
+        assert_eq!(teleports, 1);
+        assert_eq!(world.get::<&Velocity>(ball).unwrap().0, vec2(400.0, 60.0));
+        assert!(
+            last.x > 600.0,
+            "ball should have carried on out of the exit portal"
+        );
     }
 }
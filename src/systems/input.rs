@@ -1,29 +1,112 @@
-use crate::entities::{PaddleTag, Position, RectComp, Velocity};
+use crate::bindings::{Action, Controls};
+use crate::config::Config;
+use crate::constants::*;
+use crate::entities::{Arena, PaddleTag, Position, RectComp, Velocity};
+use crate::settings::{ControlMode, Settings};
 use hecs::World;
 use macroquad::prelude::*;
 
-pub fn input_system(world: &mut World, _dt: f32) {
-    let speed = 500.0;
-    for (_id, (pos, vel, rect, _)) in
-        world.query_mut::<(&mut Position, &mut Velocity, &RectComp, &PaddleTag)>()
-    {
-        let mut move_dir = 0.0;
-        if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
+/// Player intent for one frame, decoupled from where it came from.
+#[derive(Clone, Copy, Default)]
+pub struct FrameInput {
+    pub move_dir: f32, // -1.0 (left) to 1.0 (right)
+    pub launch: bool,
+    pub target_x: Option<f32>, // where the pointer wants the paddle centre, overrides move_dir
+}
+
+/// Pointer state kept between frames. Macroquad reports touches as mouse
+/// events, so this covers touch screens too.
+#[derive(Default)]
+pub struct PointerControl {
+    target_x: Option<f32>, // smoothed pointer x while the pointer is steering
+    last_mouse: Option<Vec2>,
+}
+
+impl PointerControl {
+    /// Pointer x to steer towards this frame, or `None` when the keyboard is in charge.
+    fn update(&mut self, settings: &Settings, keyboard_moved: bool, dt: f32) -> Option<f32> {
+        let mouse = Vec2::from(mouse_position());
+        let moved = self.last_mouse.is_some_and(|last| last != mouse)
+            || is_mouse_button_pressed(MouseButton::Left);
+        self.last_mouse = Some(mouse);
+
+        match settings.control_mode {
+            ControlMode::Keyboard => return None,
+            ControlMode::Hybrid if keyboard_moved => {
+                self.target_x = None;
+                return None;
+            }
+            ControlMode::Hybrid if self.target_x.is_none() && !moved => return None,
+            _ => {}
+        }
+
+        let current = self.target_x.unwrap_or(mouse.x);
+        let mut step = mouse.x - current;
+        if settings.pointer_smoothing {
+            step *= 1.0 - (-POINTER_SMOOTHING_RATE * dt).exp();
+        }
+        if settings.pointer_speed_cap {
+            let max_step = POINTER_MAX_SPEED * dt;
+            step = step.clamp(-max_step, max_step);
+        }
+        self.target_x = Some(current + step);
+        self.target_x
+    }
+}
+
+pub fn read_input(
+    controls: &Controls,
+    settings: &Settings,
+    pointer: &mut PointerControl,
+    dt: f32,
+) -> FrameInput {
+    let mut move_dir = 0.0;
+    if settings.control_mode.uses_keyboard() {
+        if controls.bindings.down(Action::MoveLeft) {
             move_dir -= 1.0;
         }
-        if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
+        if controls.bindings.down(Action::MoveRight) {
             move_dir += 1.0;
         }
+        if move_dir == 0.0 {
+            move_dir = controls.gamepads.move_x();
+        }
+    }
+    let target_x = pointer.update(settings, move_dir != 0.0, dt);
+    let clicked =
+        settings.control_mode.uses_pointer() && is_mouse_button_pressed(MouseButton::Left);
 
-        vel.0.x = move_dir * speed;
+    FrameInput {
+        move_dir,
+        launch: controls.pressed(Action::Launch) || clicked,
+        target_x,
+    }
+}
+
+pub fn input_system(
+    world: &mut World,
+    config: &Config,
+    arena: &Arena,
+    input: &FrameInput,
+    dt: f32,
+) {
+    let speed = config.paddle.speed;
+    for (_id, (pos, vel, rect, _)) in
+        world.query_mut::<(&mut Position, &mut Velocity, &RectComp, &PaddleTag)>()
+    {
+        vel.0.x = match input.target_x {
+            // Cover the distance in one tick; any smoothing already happened in the target
+            Some(target) => (target - rect.0.x / 2.0 - pos.0.x) / dt,
+            None => input.move_dir * speed,
+        };
 
         // Predictive clamping to prevent crossing the edge
-        let new_x = pos.0.x + vel.0.x * get_frame_time();
+        let new_x = pos.0.x + vel.0.x * dt;
         if new_x < 0.0 {
             pos.0.x = 0.0;
             vel.0.x = 0.0;
-        } else if new_x > screen_width() - rect.0.x {
-            pos.0.x = screen_width() - rect.0.x;
+        } else if new_x > arena.width - rect.0.x {
+            pos.0.x = arena.width - rect.0.x;
             vel.0.x = 0.0;
         }
     }
@@ -2,4 +2,5 @@ pub mod collision;
 pub mod input;
 pub mod particle;
 pub mod physics;
+pub mod powerup;
 pub mod render;
@@ -10,8 +10,8 @@ pub fn particle_system(world: &mut World, dt: f32) {
         &ParticleDamping,
         &ParticleTag,
     )>() {
-        // Friction / Damping
-        vel.0 *= damping.0;
+        // Friction / Damping (tuned per 60 Hz frame, scaled to dt)
+        vel.0 *= damping.0.powf(dt * 60.0);
 
         // Rotation
         rot.0 += rot.1 * dt;
@@ -19,6 +19,18 @@ pub fn particle_system(world: &mut World, dt: f32) {
         // Scaling (Shrink over time)
         // Here we just shrink it slightly every frame for simplicity,
         // or we could use the lifetime to be more precise.
-        size.0 *= 0.98;
+        size.0 *= 0.98f32.powf(dt * 60.0);
+    }
+
+    // Update particle and score popup lifetimes
+    let mut expired = Vec::new();
+    for (id, lifetime) in world.query_mut::<&mut ParticleLifetime>() {
+        lifetime.0 -= dt;
+        if lifetime.0 <= 0.0 {
+            expired.push(id);
+        }
+    }
+    for id in expired {
+        let _ = world.despawn(id);
     }
 }
@@ -1,8 +1,12 @@
-use crate::entities::{Position, Velocity};
+use crate::entities::{BallTag, Position, Velocity};
 use hecs::World;
 
+/// Moves everything except balls, which `collision_system` sweeps along their path.
 pub fn physics_system(world: &mut World, sub_dt: f32) {
-    for (_id, (pos, vel)) in world.query_mut::<(&mut Position, &Velocity)>() {
+    for (_id, (pos, vel)) in world
+        .query_mut::<(&mut Position, &Velocity)>()
+        .without::<&BallTag>()
+    {
         pos.0 += vel.0 * sub_dt;
     }
 }
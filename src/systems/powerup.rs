@@ -0,0 +1,206 @@
+use crate::config::Config;
+use crate::entities::*;
+use hecs::World;
+use macroquad::prelude::*;
+
+pub struct PowerUpEvents {
+    pub extra_lives: u32,
+    pub multi_ball: bool,
+}
+
+/// How much of the game's ball speed the balls currently move at.
+pub fn speed_factor(world: &mut World, config: &Config) -> f32 {
+    let slow = world
+        .query_mut::<(&ActiveEffects, &PaddleTag)>()
+        .into_iter()
+        .any(|(_id, (effects, _tag))| effects.is_active(PowerUpKind::SlowBall));
+    if slow { config.ball.slow_factor } else { 1.0 }
+}
+
+fn apply_effect(world: &mut World, config: &Config, kind: PowerUpKind, events: &mut PowerUpEvents) {
+    match kind {
+        PowerUpKind::ExtraLife => {
+            events.extra_lives += 1;
+            return;
+        }
+        PowerUpKind::MultiBall => {
+            events.multi_ball = true;
+            return;
+        }
+        _ => {}
+    }
+
+    let mut already_active = false;
+    for (_id, (effects, _tag)) in world.query_mut::<(&mut ActiveEffects, &PaddleTag)>() {
+        if let Some(timer) = effects.timers.iter_mut().find(|(k, _)| *k == kind) {
+            timer.1 = config.powerups.duration;
+            already_active = true;
+        } else {
+            effects.timers.push((kind, config.powerups.duration));
+        }
+    }
+    if already_active {
+        return;
+    }
+
+    match kind {
+        PowerUpKind::Wide => {
+            for (_id, (pos, rect, _tag)) in
+                world.query_mut::<(&mut Position, &mut RectComp, &PaddleTag)>()
+            {
+                let new_width = config.paddle.width * config.paddle.wide_factor;
+                pos.0.x -= (new_width - rect.0.x) / 2.0;
+                rect.0.x = new_width;
+            }
+        }
+        PowerUpKind::SlowBall
+        | PowerUpKind::Sticky
+        | PowerUpKind::Laser
+        | PowerUpKind::ExtraLife
+        | PowerUpKind::MultiBall => {}
+    }
+}
+
+fn revert_effect(world: &mut World, config: &Config, kind: PowerUpKind) {
+    match kind {
+        PowerUpKind::Wide => {
+            for (_id, (pos, rect, _tag)) in
+                world.query_mut::<(&mut Position, &mut RectComp, &PaddleTag)>()
+            {
+                pos.0.x += (rect.0.x - config.paddle.width) / 2.0;
+                rect.0.x = config.paddle.width;
+            }
+        }
+        PowerUpKind::Sticky => release_stuck_balls(world, config),
+        PowerUpKind::SlowBall
+        | PowerUpKind::Laser
+        | PowerUpKind::ExtraLife
+        | PowerUpKind::MultiBall => {}
+    }
+}
+
+/// Launches every ball held by a sticky paddle, angled by where it sits on the
+/// paddle. The game brings it up to speed at the end of the tick.
+fn release_stuck_balls(world: &mut World, config: &Config) {
+    let mut paddle_width = config.paddle.width;
+    for (_id, (rect, _tag)) in world.query_mut::<(&RectComp, &PaddleTag)>() {
+        paddle_width = rect.0.x;
+    }
+
+    let mut released = Vec::new();
+    for (id, (vel, stuck, _tag)) in world.query_mut::<(&mut Velocity, &StuckToPaddle, &BallTag)>() {
+        let hit_factor = (stuck.0 - paddle_width / 2.0) / (paddle_width / 2.0);
+        vel.0 = vec2(hit_factor * 1.5, 1.0).normalize();
+        released.push(id);
+    }
+    for id in released {
+        let _ = world.remove_one::<StuckToPaddle>(id);
+    }
+}
+
+/// Reverts every active timed effect, e.g. when the player loses a life.
+pub fn clear_effects(world: &mut World, config: &Config) {
+    let mut active = Vec::new();
+    for (_id, (effects, _tag)) in world.query_mut::<(&mut ActiveEffects, &PaddleTag)>() {
+        active.extend(effects.timers.drain(..).map(|(kind, _)| kind));
+        effects.laser_cooldown = 0.0;
+    }
+    for kind in active {
+        revert_effect(world, config, kind);
+    }
+}
+
+pub fn powerup_system(
+    world: &mut World,
+    config: &Config,
+    dt: f32,
+    fire: bool,
+    events: &mut PowerUpEvents,
+) {
+    let mut paddle = None;
+    for (_id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &PaddleTag)>() {
+        paddle = Some((pos.0, rect.0));
+    }
+    let Some((p_pos, p_size)) = paddle else {
+        return;
+    };
+
+    // Catch falling capsules, drop the ones that fell past the paddle
+    let mut caught = Vec::new();
+    let mut gone = Vec::new();
+    for (id, (pos, rect, power, _tag)) in
+        world.query_mut::<(&Position, &RectComp, &PowerUpComp, &CapsuleTag)>()
+    {
+        let overlaps = pos.0.x < p_pos.x + p_size.x
+            && pos.0.x + rect.0.x > p_pos.x
+            && pos.0.y < p_pos.y + p_size.y
+            && pos.0.y + rect.0.y > p_pos.y;
+        if overlaps {
+            caught.push(power.0);
+            gone.push(id);
+        } else if pos.0.y + rect.0.y < 0.0 {
+            gone.push(id);
+        }
+    }
+    for id in gone {
+        let _ = world.despawn(id);
+    }
+    for kind in caught {
+        apply_effect(world, config, kind, events);
+    }
+
+    // Tick timers and revert whatever ran out
+    let mut expired = Vec::new();
+    let mut laser_ready = false;
+    for (_id, (effects, _tag)) in world.query_mut::<(&mut ActiveEffects, &PaddleTag)>() {
+        effects.laser_cooldown = (effects.laser_cooldown - dt).max(0.0);
+        effects.timers.retain_mut(|(kind, remaining)| {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                expired.push(*kind);
+            }
+            *remaining > 0.0
+        });
+        laser_ready = effects.is_active(PowerUpKind::Laser) && effects.laser_cooldown <= 0.0;
+    }
+    for kind in expired {
+        revert_effect(world, config, kind);
+    }
+
+    // Re-read the paddle, a reverted Wide effect may have resized it
+    let mut paddle = (p_pos, p_size);
+    for (_id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &PaddleTag)>() {
+        paddle = (pos.0, rect.0);
+    }
+    let (p_pos, p_size) = paddle;
+
+    // Stuck balls ride along with the paddle
+    let mut any_stuck = false;
+    for (_id, (pos, stuck, _tag)) in
+        world.query_mut::<(&mut Position, &mut StuckToPaddle, &BallTag)>()
+    {
+        stuck.0 = stuck.0.clamp(0.0, p_size.x);
+        pos.0 = vec2(p_pos.x + stuck.0, p_pos.y + p_size.y + config.ball.radius);
+        any_stuck = true;
+    }
+
+    if !fire {
+        return;
+    }
+    if any_stuck {
+        release_stuck_balls(world, config);
+    } else if laser_ready {
+        for x in [p_pos.x + 6.0, p_pos.x + p_size.x - 10.0] {
+            world.spawn((
+                Position(vec2(x, p_pos.y + p_size.y)),
+                PrevPosition(vec2(x, p_pos.y + p_size.y)),
+                Velocity(vec2(0.0, config.powerups.laser_speed)),
+                RectComp(vec2(4.0, 14.0)),
+                LaserTag,
+            ));
+        }
+        for (_id, (effects, _tag)) in world.query_mut::<(&mut ActiveEffects, &PaddleTag)>() {
+            effects.laser_cooldown = config.powerups.laser_cooldown;
+        }
+    }
+}
@@ -3,31 +3,52 @@ use crate::entities::*;
 use hecs::World;
 use macroquad::prelude::*;
 
-pub fn render_system(world: &mut World) {
+fn lerp_position(pos: &Position, prev: Option<&PrevPosition>, alpha: f32) -> Vec2 {
+    prev.map_or(pos.0, |prev| prev.0.lerp(pos.0, alpha))
+}
+
+/// `alpha` is how far the frame sits between the previous and current tick.
+pub fn render_system(world: &mut World, alpha: f32) {
     // Draw trail first
-    for (_id, (pos, trail, _tag)) in world.query_mut::<(&Position, &mut TrailComp, &BallTag)>() {
+    for (_id, (pos, prev, trail, _tag)) in
+        world.query_mut::<(&Position, Option<&PrevPosition>, &mut TrailComp, &BallTag)>()
+    {
         // Update trail data (this could be in its own system but for simplicity...)
-        trail.positions.push_front(pos.0);
+        trail.positions.push_front(lerp_position(pos, prev, alpha));
         if trail.positions.len() > trail.max_size {
             trail.positions.pop_back();
         }
 
         for (i, p) in trail.positions.iter().enumerate() {
-            let alpha = 1.0 - (i as f32 / trail.max_size as f32);
+            let fade = 1.0 - (i as f32 / trail.max_size as f32);
             draw_circle(
                 p.x,
                 p.y,
-                10.0 * alpha * 0.8, // Ball radius approx
-                Color::from_rgba(255, 255, 255, (alpha * 100.0) as u8),
+                10.0 * fade * 0.8, // Ball radius approx
+                Color::from_rgba(255, 255, 255, (fade * 100.0) as u8),
             );
         }
     }
 
-    // Draw blocks
-    for (_id, (pos, rect, color, _tag)) in
-        world.query_mut::<(&Position, &RectComp, &ColorComp, &BlockTag)>()
-    {
-        draw_rectangle(pos.0.x, pos.0.y, rect.0.x, rect.0.y, color.0);
+    // Draw blocks (darkened and cracked as they lose health)
+    for (_id, (pos, rect, color, health, kind, _tag)) in world.query_mut::<(
+        &Position,
+        &RectComp,
+        &ColorComp,
+        &Health,
+        &BlockKind,
+        &BlockTag,
+    )>() {
+        let shade = 0.4 + 0.6 * health.hits as f32 / health.max as f32;
+        let c = Color::new(
+            color.0.r * shade,
+            color.0.g * shade,
+            color.0.b * shade,
+            color.0.a,
+        );
+        draw_rectangle(pos.0.x, pos.0.y, rect.0.x, rect.0.y, c);
+        draw_cracks(pos.0, rect.0, health.max - health.hits);
+        draw_kind_marker(pos.0, rect.0, *kind);
         draw_rectangle_lines(
             pos.0.x,
             pos.0.y,
@@ -38,25 +59,74 @@ pub fn render_system(world: &mut World) {
         );
     }
 
+    // Draw falling power-up capsules
+    for (_id, (position, prev, rect, power, _tag)) in world.query_mut::<(
+        &Position,
+        Option<&PrevPosition>,
+        &RectComp,
+        &PowerUpComp,
+        &CapsuleTag,
+    )>() {
+        let pos = lerp_position(position, prev, alpha);
+        let r = rect.0.y / 2.0;
+        let color = power.0.color();
+        draw_rectangle(pos.x + r, pos.y, rect.0.x - 2.0 * r, rect.0.y, color);
+        draw_circle(pos.x + r, pos.y + r, r, color);
+        draw_circle(pos.x + rect.0.x - r, pos.y + r, r, color);
+        draw_line(
+            pos.x + r,
+            pos.y + r,
+            pos.x + rect.0.x - r,
+            pos.y + r,
+            2.0,
+            Color::from_rgba(255, 255, 255, 120),
+        );
+    }
+
+    // Draw laser bolts
+    for (_id, (position, prev, rect, _tag)) in
+        world.query_mut::<(&Position, Option<&PrevPosition>, &RectComp, &LaserTag)>()
+    {
+        let pos = lerp_position(position, prev, alpha);
+        draw_rectangle(pos.x, pos.y, rect.0.x, rect.0.y, RED);
+    }
+
     // Draw paddle
-    for (_id, (pos, rect, _tag)) in world.query_mut::<(&Position, &RectComp, &PaddleTag)>() {
-        draw_rectangle(pos.0.x, pos.0.y, rect.0.x, rect.0.y, SKYBLUE);
+    for (_id, (position, prev, rect, effects, _tag)) in world.query_mut::<(
+        &Position,
+        Option<&PrevPosition>,
+        &RectComp,
+        &ActiveEffects,
+        &PaddleTag,
+    )>() {
+        let pos = lerp_position(position, prev, alpha);
+        draw_rectangle(pos.x, pos.y, rect.0.x, rect.0.y, SKYBLUE);
+        if effects.is_active(PowerUpKind::Laser) {
+            draw_rectangle(pos.x + 4.0, pos.y, 8.0, rect.0.y + 6.0, RED);
+            draw_rectangle(pos.x + rect.0.x - 12.0, pos.y, 8.0, rect.0.y + 6.0, RED);
+        }
+        if effects.is_active(PowerUpKind::Sticky) {
+            draw_rectangle(pos.x, pos.y + rect.0.y - 4.0, rect.0.x, 4.0, PURPLE);
+        }
     }
 
     // Draw ball
-    for (_id, (pos, _tag)) in world.query_mut::<(&Position, &BallTag)>() {
+    for (_id, (position, prev, circle, _tag)) in
+        world.query_mut::<(&Position, Option<&PrevPosition>, &CircleComp, &BallTag)>()
+    {
+        let pos = lerp_position(position, prev, alpha);
         // Bloom / Glow effect (layered circles)
         for i in 1..=5 {
-            let alpha = 0.15 / (i as f32);
-            let radius = BALL_RADIUS + (i as f32 * 4.0);
+            let glow = 0.15 / (i as f32);
+            let radius = circle.0 + (i as f32 * 4.0);
             draw_circle(
-                pos.0.x,
-                pos.0.y,
+                pos.x,
+                pos.y,
                 radius,
-                Color::from_rgba(35, 206, 250, (alpha * 255.0) as u8), // SKYBLUE
+                Color::from_rgba(35, 206, 250, (glow * 255.0) as u8), // SKYBLUE
             );
         }
-        draw_poly(pos.0.x, pos.0.y, 20, BALL_RADIUS, 0.0, WHITE);
+        draw_poly(pos.x, pos.y, 20, circle.0, 0.0, WHITE);
     }
 
     // Draw particles
@@ -85,3 +155,76 @@ pub fn render_system(world: &mut World) {
         );
     }
 }
+
+fn draw_cracks(pos: Vec2, size: Vec2, count: u32) {
+    let color = Color::from_rgba(0, 0, 0, 140);
+    for i in 0..count {
+        // Spread cracks evenly so every hit adds a visible line
+        let x = pos.x + size.x * (i as f32 + 1.0) / (count as f32 + 1.0);
+        let kink = if i % 2 == 0 { 6.0 } else { -6.0 };
+        let top = vec2(x, pos.y + size.y);
+        let mid = vec2(x + kink, pos.y + size.y * 0.5);
+        let bottom = vec2(x - kink * 0.5, pos.y);
+        draw_line(top.x, top.y, mid.x, mid.y, 2.0, color);
+        draw_line(mid.x, mid.y, bottom.x, bottom.y, 2.0, color);
+    }
+}
+
+fn draw_kind_marker(pos: Vec2, size: Vec2, kind: BlockKind) {
+    let center = pos + size / 2.0;
+    match kind {
+        BlockKind::Normal => {}
+        BlockKind::Steel => {
+            // Rivets in the corners
+            for corner in [
+                vec2(4.0, 4.0),
+                vec2(size.x - 4.0, 4.0),
+                vec2(4.0, size.y - 4.0),
+                vec2(size.x - 4.0, size.y - 4.0),
+            ] {
+                draw_circle(pos.x + corner.x, pos.y + corner.y, 2.0, LIGHTGRAY);
+            }
+        }
+        BlockKind::Explosive => {
+            let r = size.y * 0.3;
+            draw_line(
+                center.x - r,
+                center.y - r,
+                center.x + r,
+                center.y + r,
+                3.0,
+                YELLOW,
+            );
+            draw_line(
+                center.x - r,
+                center.y + r,
+                center.x + r,
+                center.y - r,
+                3.0,
+                YELLOW,
+            );
+        }
+        BlockKind::Portal(_) => {
+            draw_circle_lines(center.x, center.y, size.y * 0.35, 2.0, WHITE);
+        }
+    }
+}
+
+/// Score popups go on top in screen space: text drawn through the flipped
+/// world camera would come out upside down.
+pub fn render_popups(world: &mut World, camera: &Camera2D, alpha: f32) {
+    for (_id, (position, prev, text, color, lifetime, _tag)) in world.query_mut::<(
+        &Position,
+        Option<&PrevPosition>,
+        &PopupText,
+        &ColorComp,
+        &ParticleLifetime,
+        &PopupTag,
+    )>() {
+        let pos = camera.world_to_screen(lerp_position(position, prev, alpha));
+        let text_size = measure_text(&text.0, None, 26, 1.0);
+        let mut c = color.0;
+        c.a = (lifetime.0 / POPUP_LIFETIME * 2.0).clamp(0.0, 1.0);
+        draw_text(&text.0, pos.x - text_size.width / 2.0, pos.y, 26.0, c);
+    }
+}
@@ -0,0 +1,132 @@
+// Full games played by the headless autopilot on the shipped campaign. The
+// expected outcomes pin down gameplay: a change that moves them should be one
+// that means to.
+
+use breaker_block::config::Config;
+use breaker_block::constants::*;
+use breaker_block::entities::{Arena, BlockTag, Position};
+use breaker_block::game::Game;
+use breaker_block::headless::{self, Finish, Outcome};
+use breaker_block::level::load_campaign;
+use breaker_block::replay::{Recorder, Replay};
+use breaker_block::systems::input::FrameInput;
+
+const ARENA: Arena = Arena {
+    width: ARENA_WIDTH,
+    height: ARENA_HEIGHT,
+};
+
+fn new_game(seed: u64, arena: Arena) -> Game {
+    let campaign = load_campaign(CAMPAIGN_PATH).expect("shipped campaign loads");
+    Game::new(campaign, arena, seed, Config::default())
+}
+
+fn play(seed: u64) -> Outcome {
+    headless::run(&mut new_game(seed, ARENA), None)
+}
+
+fn recording_game(seed: u64) -> Game {
+    let mut game = new_game(seed, ARENA);
+    game.recorder = Some(Recorder::new(seed, Config::default().fingerprint(), ARENA));
+    game
+}
+
+/// Writes the game's recording to a file and reads it back.
+fn round_trip(game: &Game, name: &str) -> Replay {
+    let path = std::env::temp_dir().join(format!(
+        "breaker_block_{}_{}.bbrp",
+        name,
+        std::process::id()
+    ));
+    game.recorder.as_ref().unwrap().save(&path).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    replay
+}
+
+fn block_xs(game: &mut Game) -> Vec<f32> {
+    let mut xs: Vec<f32> = game
+        .world
+        .query_mut::<(&Position, &BlockTag)>()
+        .into_iter()
+        .map(|(_id, (pos, _tag))| pos.0.x)
+        .collect();
+    xs.sort_by(f32::total_cmp);
+    xs
+}
+
+#[test]
+fn autopilot_wins_seed_2() {
+    let outcome = play(2);
+    assert_eq!(outcome.finish, Finish::Victory, "{}", outcome);
+    assert_eq!(outcome.level, outcome.levels - 1);
+    assert_eq!((outcome.score, outcome.lives), (3530, 3), "{}", outcome);
+}
+
+#[test]
+fn autopilot_loses_seed_1() {
+    let outcome = play(1);
+    assert_eq!(outcome.finish, Finish::GameOver, "{}", outcome);
+    assert_eq!((outcome.score, outcome.lives), (2220, 0), "{}", outcome);
+}
+
+#[test]
+fn same_seed_plays_the_same_game() {
+    assert_eq!(play(5), play(5));
+}
+
+#[test]
+fn replay_matches_recorded_game() {
+    let mut game = recording_game(3);
+    let recorded = headless::run(&mut game, None);
+
+    let replay = round_trip(&game, "outcome");
+    assert_eq!(replay.config_fingerprint, Config::default().fingerprint());
+
+    let mut game = new_game(replay.seed, replay.arena);
+    let replayed = headless::run(&mut game, Some(replay));
+    assert_eq!(replayed.finish, Finish::ReplayFinished);
+    assert_eq!(
+        Outcome {
+            finish: recorded.finish,
+            ..replayed
+        },
+        recorded
+    );
+}
+
+#[test]
+fn replay_keeps_pause_menu_order() {
+    let input = FrameInput {
+        move_dir: 0.0,
+        launch: true,
+        target_x: None,
+    };
+    let mut game = recording_game(4);
+    game.start();
+    for _ in 0..2000 {
+        game.tick(&input);
+    }
+    // Restart Level from the pause menu, then resume into a resized window
+    game.restart_level();
+    game.set_arena(Arena {
+        width: ARENA_WIDTH + 200.0,
+        height: ARENA_HEIGHT,
+    });
+    for _ in 0..2000 {
+        game.tick(&input);
+    }
+    let recorded = (block_xs(&mut game), game.score);
+    assert!(recorded.1 > 0, "the ball never reached the blocks");
+
+    let mut replay = round_trip(&game, "pause");
+    let mut game = new_game(replay.seed, replay.arena);
+    game.start();
+    while let Some(step) = replay.next_step() {
+        for event in step.events {
+            event.apply(&mut game);
+        }
+        game.tick(&step.input);
+    }
+    assert_eq!((block_xs(&mut game), game.score), recorded);
+}